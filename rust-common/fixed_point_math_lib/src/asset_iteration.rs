@@ -0,0 +1,20 @@
+// Hard cap on the number of assets that can be iterated over, regardless of the maximum given by the caller.
+pub const MAX_ASSET_ITERATIONS: usize = 16;
+
+// Invokes f with the index of each of the first asset_count assets, stopping at the first error returned by f. If
+// asset_count exceeds max_assets (or MAX_ASSET_ITERATIONS), the error built by on_exceeded is returned without invoking f,
+// so that a corrupted asset count cannot cause an unbounded iteration.
+pub fn for_each_asset<E>(
+    asset_count: usize,
+    max_assets: usize,
+    on_exceeded: impl FnOnce() -> E,
+    mut f: impl FnMut(usize) -> Result<(), E>
+) -> Result<(), E> {
+    if asset_count > max_assets.min(MAX_ASSET_ITERATIONS) { return Err(on_exceeded()) };
+
+    for asset_index in 0..asset_count {
+        f(asset_index)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,21 @@
+use crate::mul_div::mul_div;
+
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ComputeFeeError {
+    InvalidFee,
+    Overflow
+}
+
+// Fees are always rounded up (i.e. the user's output is rounded down), so that rounding never works against the protocol.
+// The intermediate product is computed with U256 precision, hence the computation cannot overflow for any u64 amount.
+pub fn compute_fee(amount: u64, fee_bps: u64) -> Result<u64, ComputeFeeError> {
+    if fee_bps > BPS_DENOMINATOR { return Err(ComputeFeeError::InvalidFee) };
+
+    // Flooring the user's share is equivalent to rounding the fee up
+    let amount_after_fee = mul_div(amount, BPS_DENOMINATOR - fee_bps, BPS_DENOMINATOR)
+        .map_err(|_| ComputeFeeError::Overflow)?;
+
+    Ok(amount - amount_after_fee)   // amount_after_fee <= amount, the subtraction cannot underflow
+}
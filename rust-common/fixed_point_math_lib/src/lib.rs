@@ -1,5 +1,8 @@
 pub mod fixed_point_math_x64;
 pub mod u256;
+pub mod fee_math;
+pub mod mul_div;
+pub mod asset_iteration;
 
 #[cfg(test)]
 pub mod test {
@@ -12,4 +15,10 @@ pub mod test {
     pub mod test_mul;
     pub mod test_div;
     pub mod test_pow;
+
+    pub mod test_u256;
+
+    pub mod test_fee_math;
+    pub mod test_mul_div;
+    pub mod test_asset_iteration;
 }
\ No newline at end of file
@@ -0,0 +1,19 @@
+use crate::u256::U256;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MulDivError {
+    DivisionByZero,
+    Overflow
+}
+
+// Computes floor(a * b / denominator). The intermediate product is computed with U256 precision, hence it cannot overflow;
+// an error is returned only if the denominator is zero or if the result does not fit in a u64.
+pub fn mul_div(a: u64, b: u64, denominator: u64) -> Result<u64, MulDivError> {
+    if denominator == 0 { return Err(MulDivError::DivisionByZero) };
+
+    let result = U256::from(a) * U256::from(b) / U256::from(denominator);
+
+    if result > U256::from(u64::MAX) { return Err(MulDivError::Overflow) };
+
+    Ok(result.as_u64())
+}
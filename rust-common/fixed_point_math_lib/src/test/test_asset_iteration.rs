@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod test_asset_iteration {
+    use crate::asset_iteration::{for_each_asset, MAX_ASSET_ITERATIONS};
+
+
+
+    // Test for_each_asset ******************************************************************************************************
+
+    #[test]
+    fn test_for_each_asset_visits_all_assets() {
+        let mut visited: Vec<usize> = vec![];
+
+        assert_eq!(for_each_asset(3, 3, || "exceeded", |asset_index| { visited.push(asset_index); Ok(()) }), Ok(()));
+        assert_eq!(visited, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_for_each_asset_no_assets() {
+        assert_eq!(for_each_asset(0, 3, || "exceeded", |_| Err("invoked")), Ok(()));
+    }
+
+    #[test]
+    fn test_for_each_asset_stops_on_error() {
+        let mut visited: Vec<usize> = vec![];
+
+        assert_eq!(
+            for_each_asset(3, 3, || "exceeded", |asset_index| {
+                visited.push(asset_index);
+                if asset_index == 1 { Err("failed") } else { Ok(()) }
+            }),
+            Err("failed")
+        );
+        assert_eq!(visited, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_for_each_asset_max_assets_exceeded() {
+        let mut visited: Vec<usize> = vec![];
+
+        assert_eq!(for_each_asset(4, 3, || "exceeded", |asset_index| { visited.push(asset_index); Ok(()) }), Err("exceeded"));
+        assert!(visited.is_empty());
+    }
+
+    #[test]
+    fn test_for_each_asset_hard_cap() {
+        // The hard cap applies even if the given maximum is larger
+        assert_eq!(for_each_asset(MAX_ASSET_ITERATIONS, usize::MAX, || "exceeded", |_| Ok(())), Ok(()));
+        assert_eq!(for_each_asset(MAX_ASSET_ITERATIONS + 1, usize::MAX, || "exceeded", |_| Ok(())), Err("exceeded"));
+
+        // A corrupted (huge) asset count is rejected without iterating
+        assert_eq!(for_each_asset(usize::MAX, usize::MAX, || "exceeded", |_| Err("invoked")), Err("exceeded"));
+    }
+
+}
@@ -0,0 +1,48 @@
+#[cfg(test)]
+mod test_fee_math {
+    use crate::fee_math::{compute_fee, ComputeFeeError, BPS_DENOMINATOR};
+
+
+
+    // Test compute_fee *********************************************************************************************************
+
+    #[test]
+    fn test_compute_fee_exact() {
+        assert_eq!(compute_fee(0, 30), Ok(0));
+        assert_eq!(compute_fee(10_000, 30), Ok(30));
+        assert_eq!(compute_fee(12_345, 0), Ok(0));
+        assert_eq!(compute_fee(12_345, BPS_DENOMINATOR), Ok(12_345));
+    }
+
+    #[test]
+    fn test_compute_fee_rounds_up() {
+        // Any fractional fee is rounded up to the next unit
+        assert_eq!(compute_fee(1, 1), Ok(1));
+        assert_eq!(compute_fee(9_999, 1), Ok(1));
+        assert_eq!(compute_fee(10_001, 1), Ok(2));
+        assert_eq!(compute_fee(19_999, 5_000), Ok(10_000));
+
+        // The user's share (amount - fee) is hence never larger than the exact one
+        for amount in [1u64, 3, 7, 9_999, 10_001, 123_456_789] {
+            for fee_bps in [1u64, 3, 30, 333, 5_000, 9_999] {
+                let fee = compute_fee(amount, fee_bps).unwrap();
+
+                assert!((fee as u128) * (BPS_DENOMINATOR as u128) >= (amount as u128) * (fee_bps as u128));
+                assert!(((fee - 1) as u128) * (BPS_DENOMINATOR as u128) < (amount as u128) * (fee_bps as u128));
+            }
+        }
+    }
+
+    #[test]
+    fn test_compute_fee_max_amount() {
+        // The intermediate product overflows u64, but not the U256 used for the computation
+        assert_eq!(compute_fee(u64::MAX, BPS_DENOMINATOR), Ok(u64::MAX));
+        assert_eq!(compute_fee(u64::MAX, 1), Ok(u64::MAX / BPS_DENOMINATOR + 1));
+    }
+
+    #[test]
+    fn test_compute_fee_invalid_fee() {
+        assert_eq!(compute_fee(1_000, BPS_DENOMINATOR + 1), Err(ComputeFeeError::InvalidFee));
+    }
+
+}
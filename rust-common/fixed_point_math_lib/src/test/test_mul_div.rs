@@ -0,0 +1,42 @@
+#[cfg(test)]
+mod test_mul_div {
+    use crate::mul_div::{mul_div, MulDivError};
+
+
+
+    // Test mul_div *************************************************************************************************************
+
+    #[test]
+    fn test_mul_div_exact() {
+        assert_eq!(mul_div(0, 123, 7), Ok(0));
+        assert_eq!(mul_div(6, 7, 3), Ok(14));
+        assert_eq!(mul_div(1_000, 86_400, 86_400), Ok(1_000));
+    }
+
+    #[test]
+    fn test_mul_div_rounds_down() {
+        assert_eq!(mul_div(1, 1, 2), Ok(0));
+        assert_eq!(mul_div(10, 10, 3), Ok(33));
+        assert_eq!(mul_div(999, 1, 1_000), Ok(0));
+    }
+
+    #[test]
+    fn test_mul_div_large_intermediate() {
+        // The intermediate products overflow u64, but the results fit
+        assert_eq!(mul_div(u64::MAX, u64::MAX, u64::MAX), Ok(u64::MAX));
+        assert_eq!(mul_div(u64::MAX, 86_399, 86_400), Ok(((u64::MAX as u128) * 86_399 / 86_400) as u64));
+        assert_eq!(mul_div(1 << 63, 4, 8), Ok(1 << 62));
+    }
+
+    #[test]
+    fn test_mul_div_result_overflow() {
+        assert_eq!(mul_div(u64::MAX, 2, 1), Err(MulDivError::Overflow));
+        assert_eq!(mul_div(u64::MAX, u64::MAX, u64::MAX - 1), Err(MulDivError::Overflow));
+    }
+
+    #[test]
+    fn test_mul_div_zero_denominator() {
+        assert_eq!(mul_div(1, 1, 0), Err(MulDivError::DivisionByZero));
+    }
+
+}
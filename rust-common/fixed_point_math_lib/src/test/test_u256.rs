@@ -0,0 +1,96 @@
+#[cfg(test)]
+mod test_u256 {
+    use crate::u256::{U256, ParseU256Error};
+    use crate::fixed_point_math_x64::U256_MAX;
+
+
+
+    // Test parse_dec_str *******************************************************************************************************
+
+    #[test]
+    fn test_parse_dec_str() {
+        assert_eq!(U256::parse_dec_str("0"), Ok(U256::zero()));
+        assert_eq!(U256::parse_dec_str("000123"), Ok(U256::from(123)));
+        assert_eq!(U256::parse_dec_str("18446744073709551616"), Ok(U256([0, 1, 0, 0])));
+    }
+
+    #[test]
+    fn test_parse_dec_str_max_value() {
+        let max = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+
+        assert_eq!(U256::parse_dec_str(max), Ok(U256_MAX));
+        assert_eq!(U256_MAX.to_string(), max);
+    }
+
+    #[test]
+    fn test_parse_dec_str_overflow() {
+        assert_eq!(
+            U256::parse_dec_str("115792089237316195423570985008687907853269984665640564039457584007913129639936"),
+            Err(ParseU256Error::Overflow)
+        );
+        assert_eq!(
+            U256::parse_dec_str("1000000000000000000000000000000000000000000000000000000000000000000000000000000"),
+            Err(ParseU256Error::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_parse_dec_str_malformed() {
+        assert_eq!(U256::parse_dec_str(""), Err(ParseU256Error::Empty));
+        assert_eq!(U256::parse_dec_str("-1"), Err(ParseU256Error::InvalidCharacter));
+        assert_eq!(U256::parse_dec_str("1.5"), Err(ParseU256Error::InvalidCharacter));
+        assert_eq!(U256::parse_dec_str(" 1"), Err(ParseU256Error::InvalidCharacter));
+        assert_eq!(U256::parse_dec_str("0x1"), Err(ParseU256Error::InvalidCharacter));
+    }
+
+
+
+    // Test parse_hex_str *******************************************************************************************************
+
+    #[test]
+    fn test_parse_hex_str() {
+        assert_eq!(U256::parse_hex_str("0"), Ok(U256::zero()));
+        assert_eq!(U256::parse_hex_str("0x1f"), Ok(U256::from(31)));
+        assert_eq!(U256::parse_hex_str("1F"), Ok(U256::from(31)));
+        assert_eq!(U256::parse_hex_str("0x10000000000000000"), Ok(U256([0, 1, 0, 0])));
+    }
+
+    #[test]
+    fn test_parse_hex_str_max_value() {
+        let max = format!("0x{}", "f".repeat(64));
+
+        assert_eq!(U256::parse_hex_str(&max), Ok(U256_MAX));
+        assert_eq!(U256::parse_hex_str(&format!("0x00{}", "f".repeat(64))), Ok(U256_MAX));    // Leading zeros
+        assert_eq!(U256_MAX.to_hex_string(), max);
+    }
+
+    #[test]
+    fn test_parse_hex_str_overflow() {
+        assert_eq!(U256::parse_hex_str(&format!("0x1{}", "0".repeat(64))), Err(ParseU256Error::Overflow));
+        assert_eq!(U256::parse_hex_str(&"f".repeat(65)), Err(ParseU256Error::Overflow));
+    }
+
+    #[test]
+    fn test_parse_hex_str_malformed() {
+        assert_eq!(U256::parse_hex_str(""), Err(ParseU256Error::Empty));
+        assert_eq!(U256::parse_hex_str("0x"), Err(ParseU256Error::Empty));
+        assert_eq!(U256::parse_hex_str("0xg"), Err(ParseU256Error::InvalidCharacter));
+        assert_eq!(U256::parse_hex_str("-0x1"), Err(ParseU256Error::InvalidCharacter));
+        assert_eq!(U256::parse_hex_str("0x0x1"), Err(ParseU256Error::InvalidCharacter));
+    }
+
+
+
+    // Test to_hex_string *******************************************************************************************************
+
+    #[test]
+    fn test_to_hex_string() {
+        assert_eq!(U256::zero().to_hex_string(), "0x0");
+        assert_eq!(U256::from(31).to_hex_string(), "0x1f");
+
+        let value = U256([1, 2, 3, 4]);
+        assert_eq!(U256::parse_hex_str(&value.to_hex_string()), Ok(value));
+        assert_eq!(U256::parse_dec_str(&value.to_string()), Ok(value));
+    }
+
+}
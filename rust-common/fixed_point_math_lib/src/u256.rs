@@ -2,4 +2,48 @@ use uint::construct_uint;
 
 construct_uint! {
     pub struct U256(4);
-}
\ No newline at end of file
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseU256Error {
+    Empty,
+    InvalidCharacter,
+    Overflow
+}
+
+impl U256 {
+
+    /// Parse a decimal string. Unlike `from_dec_str`, an empty string is rejected.
+    pub fn parse_dec_str(value: &str) -> Result<U256, ParseU256Error> {
+        if value.is_empty() { return Err(ParseU256Error::Empty) }
+
+        U256::from_dec_str(value).map_err(|err| match err {
+            uint::FromDecStrErr::InvalidCharacter => ParseU256Error::InvalidCharacter,
+            uint::FromDecStrErr::InvalidLength    => ParseU256Error::Overflow
+        })
+    }
+
+    /// Parse a hex string, with or without the '0x' prefix. Leading zeros are allowed.
+    pub fn parse_hex_str(value: &str) -> Result<U256, ParseU256Error> {
+        let value = value.strip_prefix("0x").unwrap_or(value);
+
+        if value.is_empty() { return Err(ParseU256Error::Empty) }
+
+        let mut result = U256::zero();
+        for c in value.chars() {
+            let digit = c.to_digit(16).ok_or(ParseU256Error::InvalidCharacter)?;
+
+            // Shifting by 4 bits would drop the most significant nibble
+            if result.0[3] >> 60 != 0 { return Err(ParseU256Error::Overflow) }
+
+            result = (result << 4) | U256::from(digit);
+        }
+
+        Ok(result)
+    }
+
+    /// Lowercase hex representation with the '0x' prefix (e.g. 0x1f).
+    pub fn to_hex_string(&self) -> String {
+        format!("{:#x}", self)
+    }
+}
@@ -7,6 +7,8 @@ use std::convert::TryInto;
 
 use spl_token::ID as TOKEN_PROGRAM_ID;
 
+use shared_lib::u256::U256;
+
 use polymerase_register::cpi::accounts::RegisterPort;
 use polymerase_register::{program::PolymeraseRegister};
 use polymerase_sender::cpi::accounts::SendIbcPacket;
@@ -28,6 +30,23 @@ const CTX_ASSET_APPROX_SWAP     : u8 = 0x01;
 const CTX_LIQUIDITY_SWAP        : u8 = 0x02;
 const CTX_LIQUIDITY_APPROX_SWAP : u8 = 0x03;
 
+// Define packet timeouts (in slots, ~400ms each)
+const DEFAULT_TIMEOUT_BLOCKS    : u64 = 9_000;         // ~1 hour
+const MIN_TIMEOUT_BLOCKS        : u64 = 150;           // ~1 minute
+const MAX_TIMEOUT_BLOCKS        : u64 = 1_512_000;     // ~1 week
+
+// Define payload lengths
+const PAYLOAD_HEADER_LENGTH         : usize = 65;     // Context + source pool + target pool
+const ASSET_SWAP_PAYLOAD_LENGTH     : usize = 166;
+const LIQUIDITY_SWAP_PAYLOAD_LENGTH : usize = 130;
+
+// Swap pool state layout (shared by all pool types): discriminator, setup_master, dao_authority, ibc_interface, pool_assets_mints
+const POOL_ASSETS_MINTS_OFFSET      : usize = 8 + 32*3;
+const POOL_MAX_ASSETS               : usize = 3;
+
+// Blocklist
+const MAX_BLOCKLISTED_POOLS         : usize = 8;
+
 #[program]
 pub mod ibc_interface {
 
@@ -43,6 +62,9 @@ pub mod ibc_interface {
         ctx.accounts.interface_state_account.swap_pool = swap_pool_state;
         ctx.accounts.interface_state_account.swap_pool_authority = swap_pool_authority;
         ctx.accounts.interface_state_account.polymerase_endpoint_program = polymerase_endpoint_program;
+        ctx.accounts.interface_state_account.max_units_per_packet = None;
+        ctx.accounts.interface_state_account.default_timeout_blocks = DEFAULT_TIMEOUT_BLOCKS;
+        ctx.accounts.interface_state_account.blocklisted_pools = [Pubkey::default(); MAX_BLOCKLISTED_POOLS];
         ctx.accounts.interface_state_account.interface_state_account_bump = ctx.bumps.get("interface_state_account").unwrap().to_owned();
         Ok(())
     }
@@ -52,6 +74,36 @@ pub mod ibc_interface {
         Ok(())
     }
 
+    pub fn set_max_units_per_packet(
+        ctx: Context<SetMaxUnitsPerPacket>,
+        max_units_per_packet: Option<[u64; 4]>     // None disables the cap
+    ) -> Result<()> {
+        ctx.accounts.interface_state_account.max_units_per_packet = max_units_per_packet;
+        Ok(())
+    }
+
+    pub fn set_default_timeout(
+        ctx: Context<SetDefaultTimeout>,
+        timeout_blocks: u64
+    ) -> Result<()> {
+
+        if timeout_blocks < MIN_TIMEOUT_BLOCKS || timeout_blocks > MAX_TIMEOUT_BLOCKS {
+            return Err(error!(ErrorCode::InvalidTimeout));
+        }
+
+        ctx.accounts.interface_state_account.default_timeout_blocks = timeout_blocks;
+        Ok(())
+    }
+
+    // Blocks (or unblocks) the packets sent by the given counterparty pool, regardless of the connection they arrive through
+    pub fn set_pool_blocklisted(
+        ctx: Context<SetPoolBlocklisted>,
+        pool: Pubkey,
+        blocklisted: bool
+    ) -> Result<()> {
+        ctx.accounts.interface_state_account.set_pool_blocklisted(pool, blocklisted)
+    }
+
     // ! TODO must be required before completing setup
     pub fn register_receiver(
         ctx: Context<RegisterReceiver>
@@ -109,10 +161,14 @@ pub mod ibc_interface {
 
     pub fn create_connection(
         ctx: Context<CreateConnection>,
-        chain: u64,                     // Used in the CreateConnection context
-        pool: Pubkey,                   // Used in the CreateConnection context
+        chain: u64,                     // Used in the CreateConnection context and saved to connection_state_account
+        pool: Pubkey,                   // Used in the CreateConnection context and saved to connection_state_account
         interface_program: Pubkey       // target crosschain program
     ) -> Result<()> {
+        // Store the connection parameters on the account, so that the connections of an interface can be enumerated
+        ctx.accounts.connection_state_account.interface_state_account       = ctx.accounts.interface_state_account.key();
+        ctx.accounts.connection_state_account.chain                         = chain;
+        ctx.accounts.connection_state_account.pool                          = pool;
         ctx.accounts.connection_state_account.connected_interface_program   = interface_program;
         ctx.accounts.connection_state_account.connection_state_account_bump = ctx.bumps.get("connection_state_account").unwrap().to_owned();
         Ok(())
@@ -148,7 +204,7 @@ pub mod ibc_interface {
 
         // ! The target pool connection state (chain + pool) is checked in the context
 
-        let mut message = vec![0; 166];
+        let mut message = vec![0; ASSET_SWAP_PAYLOAD_LENGTH];
 
         match approx {
             false => message[0] = CTX_ASSET_SWAP,
@@ -204,7 +260,11 @@ pub mod ibc_interface {
         );
         let channel_id: [u8; 32] = [1; 32]; // ! TODO channel_id from connection_state_account, linked to 'chain' and 'connection_state_account.connected_interface_program'
 
-        let timeout_block_height: u64 = 0;
+        let timeout_block_height: u64 = Clock::get()?.slot
+            .checked_add(ctx.accounts.interface_state_account.default_timeout_blocks).unwrap();
+
+        ctx.accounts.emit_if_loopback(chain, target_pool);
+
         polymerase_sender::cpi::send_ibc_packet(cpi_ctx, channel_id, message, timeout_block_height)    // ! TODO must add dynamic accounts
 
     }
@@ -237,7 +297,7 @@ pub mod ibc_interface {
 
         assert!(!approx, "Cross chain liquidity swap does not support approx mode.");
 
-        let mut message = vec![0; 130];
+        let mut message = vec![0; LIQUIDITY_SWAP_PAYLOAD_LENGTH];
 
         message[0] = CTX_LIQUIDITY_SWAP;     // Context
 
@@ -278,7 +338,11 @@ pub mod ibc_interface {
         );
         let channel_id: [u8; 32] = [1; 32]; // ! TODO channel_id from connection_state_account, linked to 'chain' and 'connection_state_account.connected_interface_program'
 
-        let timeout_block_height: u64 = 0;
+        let timeout_block_height: u64 = Clock::get()?.slot
+            .checked_add(ctx.accounts.interface_state_account.default_timeout_blocks).unwrap();
+
+        ctx.accounts.emit_if_loopback(chain, target_pool);
+
         polymerase_sender::cpi::send_ibc_packet(cpi_ctx, channel_id, message, timeout_block_height)    // ! TODO must add dynamic accounts
 
     }
@@ -289,118 +353,14 @@ pub mod ibc_interface {
         message: Vec<u8>
     ) -> Result<()> {
 
-        // ! TODO:
-        // ! PolymeraseInterface authority verification in OnRecvPacket context
-        // !    ==> Only allow the authorized PolymeraseInterface authority holder to execute this function
-
-        // let chain //TODO
-
-        let context: u8 = u8::from_be_bytes(message[..1].try_into().unwrap());
-        let source_pool: Pubkey = Pubkey::new(&message[1..33]);
-        let target_pool: Pubkey = Pubkey::new(&message[33..65]);
-
-        //TODO move to context?
-        // Check the provided SwapPool program is the expected one
-        if !ctx.accounts.swap_pool_program.key.eq(&ctx.accounts.swap_pool.owner) {
-            return Err(error!(ErrorCode::InvalidSwapPoolProgram));
-        }
-
-        //TODO!
-        // //TODO move to context?
-        // // Check the source chain + pool is valid, i.e. it is a connected and enabled pool connection
-        // if !ctx.accounts.connection_state_account.state {
-        //     return Err(error!(ErrorCode::SourcePoolNotConnected));
-        // }
-
-        match context {
-
-            CTX_ASSET_SWAP | CTX_ASSET_APPROX_SWAP => {
-                // Invoke in_swap of the target pool
-                /*
-                    Message Format
-                    0       context            : u8
-                    1-32    source_pool        : Pubkey
-                    33-64   target_pool        : Pubkey
-                    65-96   destination        : Pubkey
-                    97-128  units              : u256 (as 4 u64)
-                    129     target_asset_index : u8
-                    130-161 min_output         : u256 (as 4 u64)
-                    162-165 source_swap_id     : u32
-                */
-    
-                let units_x64: [u64; 4] = [                                         // TODO create helper function
-                    u64::from_be_bytes(message[97..105].try_into().unwrap()),
-                    u64::from_be_bytes(message[105..113].try_into().unwrap()),
-                    u64::from_be_bytes(message[113..121].try_into().unwrap()),
-                    u64::from_be_bytes(message[121..129].try_into().unwrap())
-                ];
-
-
-                // Min output: for Solana, the maximum value allowed is u64
-                let min_output: u64 = u64::from_be_bytes(message[130..138].try_into().unwrap());
-
-                // Make sure that the rest of the 'min_output' value is zero
-                if !message[138..162].iter().all(|slice| slice == &0) {
-                    return Err(error!(ErrorCode::MinimumOutputExceedsMaxAllowed));
-                }
-
-
-                // ! VERY IMPORTANT verify provided output_asset_wallet matches the one specified in the message
-                if Pubkey::new(&message[65..97]).ne(&ctx.remaining_accounts[1].key()) {
-                    return Err(error!(ErrorCode::InvalidAssetWalletAccount));
-                }
-
-
-                ctx.accounts.invoke_in_swap(
-                    message[129],
-                    units_x64,
-                    min_output,
-                    context == CTX_ASSET_APPROX_SWAP,
-                    ctx.remaining_accounts[0].to_account_info(),
-                    ctx.remaining_accounts[1].to_account_info(),
-                    ctx.remaining_accounts[2].to_account_info(),
-                    ctx.remaining_accounts[3].to_account_info(),
-                ).unwrap();
-    
-                Ok(())
-
-            },
-
-            CTX_LIQUIDITY_SWAP => {
-                // Invoke in_liquidity_swap of the target pool
-                /*
-                    Message Format
-                    0       context            : u8
-                    1-32    source_pool        : Pubkey
-                    33-64   target_pool        : Pubkey
-                    65-96   destination        : Pubkey
-                    97-128  units              : u256 (as 4 u64)
-                */
-    
-                // CPI - Arguments 
-                let liquidity_units_x64: [u64; 4] = [
-                    u64::from_be_bytes(message[97..105].try_into().unwrap()),
-                    u64::from_be_bytes(message[105..113].try_into().unwrap()),
-                    u64::from_be_bytes(message[113..121].try_into().unwrap()),
-                    u64::from_be_bytes(message[121..129].try_into().unwrap())
-                ];
-
-                // ! VERY IMPORTANT verify provided destination_pool_token_wallet matches the one specified in the message
-                if Pubkey::new(&message[65..97]).ne(&ctx.remaining_accounts[1].key()) {
-                    return Err(error!(ErrorCode::InvalidPoolTokenWalletAccount));
-                }
-
-                ctx.accounts.invoke_liquidity_in_swap(
-                    liquidity_units_x64,
-                    ctx.remaining_accounts[0].to_account_info(),
-                    ctx.remaining_accounts[1].to_account_info(),
-                    ctx.remaining_accounts[2].to_account_info()
-                ).unwrap();
-
-                Ok(())
-            },
-            _ => Err(error!(ErrorCode::InvalidContext))
-        }
+        // Tag the failures with their category, so that decode, validation and pool errors can be told apart
+        ctx.accounts.process_recv_packet(ctx.remaining_accounts, &message).map_err(|error| {
+            emit!(RecvPacketFailEvent {
+                interface: ctx.accounts.interface_state_account.key(),
+                fail_reason: RecvPacketFailReason::from_error(&error)
+            });
+            error
+        })
 
     }
 
@@ -414,8 +374,12 @@ pub mod ibc_interface {
         // ! PolymeraseInterface authority verification in OnRecvPacket context
         // !    ==> Only allow the authorized PolymeraseInterface authority holder to execute this function
 
-        let context: u8 = u8::from_be_bytes(message[..1].try_into().unwrap());
-        let source_swap_id: u32 = u32::from_be_bytes(message[162..166].try_into().unwrap());
+        // Corrupt packets must error (rather than panic) while being decoded
+        if message.is_empty() {
+            return Err(error!(ErrorCode::InvalidPayloadLength));
+        }
+
+        let context: u8 = message[0];
 
         //TODO move to context?
         // Check the provided SwapPool program is the expected one
@@ -439,11 +403,18 @@ pub mod ibc_interface {
                     162-165 source_swap_id     : u32
                 */
 
+                if message.len() != ASSET_SWAP_PAYLOAD_LENGTH {
+                    return Err(error!(ErrorCode::InvalidPayloadLength));
+                }
+
+                let source_swap_id: u32 = u32::from_be_bytes(message[162..166].try_into().unwrap());
+
                 ctx.accounts.invoke_out_swap_ack(
                     source_swap_id,
+                    ctx.remaining_accounts[2].to_owned(),
                     ctx.remaining_accounts[3].to_owned(),
                     ctx.remaining_accounts[4].to_owned(),
-                ).unwrap();
+                )?;
 
                 Ok(())
             },
@@ -476,8 +447,12 @@ pub mod ibc_interface {
         // ! PolymeraseInterface authority verification in OnRecvPacket context
         // !    ==> Only allow the authorized PolymeraseInterface authority holder to execute this function
 
-        let context: u8 = u8::from_be_bytes(message[..1].try_into().unwrap());
-        let source_swap_id: u32 = u32::from_be_bytes(message[162..166].try_into().unwrap());
+        // Corrupt packets must error (rather than panic) while being decoded
+        if message.is_empty() {
+            return Err(error!(ErrorCode::InvalidPayloadLength));
+        }
+
+        let context: u8 = message[0];
 
         //TODO move to context?
         // Check the provided SwapPool program is the expected one
@@ -501,6 +476,12 @@ pub mod ibc_interface {
                     162-165 source_swap_id     : u32
                 */
 
+                if message.len() != ASSET_SWAP_PAYLOAD_LENGTH {
+                    return Err(error!(ErrorCode::InvalidPayloadLength));
+                }
+
+                let source_swap_id: u32 = u32::from_be_bytes(message[162..166].try_into().unwrap());
+
                 ctx.accounts.invoke_out_swap_timeout(
                     source_swap_id,
                     ctx.remaining_accounts[0].to_owned(),
@@ -508,7 +489,7 @@ pub mod ibc_interface {
                     ctx.remaining_accounts[2].to_owned(),
                     ctx.remaining_accounts[3].to_owned(),
                     ctx.remaining_accounts[4].to_owned(),
-                ).unwrap();
+                )?;
 
                 Ok(())
             },
@@ -531,6 +512,45 @@ pub mod ibc_interface {
 
     }
 
+
+    // Queries ******************************************************************************************************************
+
+    pub fn version(_ctx: Context<VersionQuery>) -> Result<VersionResponse> {
+        Ok(VersionResponse {
+            contract: env!("CARGO_PKG_NAME").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string()
+        })
+    }
+
+    // NOTE: Destination accounts are always encoded as 32 byte public keys and calldata is not supported, hence the size
+    // of the asset swap packets is constant.
+    pub fn estimate_send_asset_packet_size(_ctx: Context<EstimatePacketSize>) -> Result<u32> {
+        Ok(ASSET_SWAP_PAYLOAD_LENGTH as u32)
+    }
+
+    pub fn default_timeout(ctx: Context<DefaultTimeout>) -> Result<u64> {
+        Ok(ctx.accounts.interface_state_account.default_timeout_blocks)
+    }
+
+    pub fn is_blocklisted(ctx: Context<BlocklistQuery>, pool: Pubkey) -> Result<bool> {
+        Ok(ctx.accounts.interface_state_account.is_pool_blocklisted(&pool))
+    }
+
+    pub fn connection_state(
+        ctx: Context<ConnectionStateQuery>,
+        _chain: u64,                    // Used in the ConnectionStateQuery context
+        _pool: Pubkey                   // Used in the ConnectionStateQuery context
+    ) -> Result<ConnectionStateResponse> {
+        let connection_state_account = &ctx.accounts.connection_state_account;
+
+        Ok(ConnectionStateResponse {
+            chain: connection_state_account.chain,
+            pool: connection_state_account.pool,
+            connected_interface_program: connection_state_account.connected_interface_program,
+            enabled: !connection_state_account.connected_interface_program.eq(&Pubkey::default())
+        })
+    }
+
 }
 
 
@@ -558,6 +578,27 @@ pub struct SetConfigurator<'info> {
     pub configurator: Signer<'info>
 }
 
+#[derive(Accounts)]
+pub struct SetMaxUnitsPerPacket<'info> {
+    #[account(mut, has_one = configurator @ ErrorCode::InvalidConfigurator)]
+    pub interface_state_account: Account<'info, CrossChainSwapInterfaceState>,
+    pub configurator: Signer<'info>
+}
+
+#[derive(Accounts)]
+pub struct SetDefaultTimeout<'info> {
+    #[account(mut, has_one = configurator @ ErrorCode::InvalidConfigurator)]
+    pub interface_state_account: Account<'info, CrossChainSwapInterfaceState>,
+    pub configurator: Signer<'info>
+}
+
+#[derive(Accounts)]
+pub struct SetPoolBlocklisted<'info> {
+    #[account(mut, has_one = configurator @ ErrorCode::InvalidConfigurator)]
+    pub interface_state_account: Account<'info, CrossChainSwapInterfaceState>,
+    pub configurator: Signer<'info>
+}
+
 
 #[derive(Accounts)]
 pub struct RegisterReceiver<'info> {
@@ -582,6 +623,43 @@ pub struct RegisterReceiver<'info> {
 }
 
 
+#[derive(Accounts)]
+pub struct EstimatePacketSize<'info> {
+    pub interface_state_account: Account<'info, CrossChainSwapInterfaceState>
+}
+
+#[derive(Accounts)]
+pub struct VersionQuery<'info> {
+    pub interface_state_account: Account<'info, CrossChainSwapInterfaceState>
+}
+
+#[derive(Accounts)]
+pub struct DefaultTimeout<'info> {
+    pub interface_state_account: Account<'info, CrossChainSwapInterfaceState>
+}
+
+#[derive(Accounts)]
+pub struct BlocklistQuery<'info> {
+    pub interface_state_account: Account<'info, CrossChainSwapInterfaceState>
+}
+
+
+#[derive(Accounts)]
+#[instruction(chain: u64, pool: Pubkey)]
+pub struct ConnectionStateQuery<'info> {
+    pub interface_state_account: Account<'info, CrossChainSwapInterfaceState>,
+    #[account(
+        seeds = [
+            &interface_state_account.key().to_bytes(),
+            chain.to_le_bytes().as_ref(),
+            &pool.to_bytes()
+        ],
+        bump = connection_state_account.connection_state_account_bump
+    )]
+    pub connection_state_account: Account<'info, ConnectionState>
+}
+
+
 #[derive(Accounts)]
 #[instruction(chain: u64, pool: Pubkey)]
 pub struct CreateConnection<'info> {
@@ -636,6 +714,22 @@ pub struct CrossChainSwap<'info> {
     pub system_program: UncheckedAccount<'info> // Intentionally not Program<'info, System>, as we do not want to run any checks on the account (and hence minimize gas fees)
 }
 
+impl<'info> CrossChainSwap<'info> {
+
+    // Packets sent to a pool connected through this same interface program never leave the chain. They cannot be processed
+    // synchronously, as the receiving end requires the target pool accounts (not available here) and would have to re-enter
+    // the sending pool program, which Solana does not allow. Flag them instead, so that they can be told apart.
+    pub fn emit_if_loopback(&self, chain: u64, target_pool: Pubkey) {
+        if self.connection_state_account.connected_interface_program == crate::ID {
+            emit!(LoopbackPacketEvent {
+                interface: self.interface_state_account.key(),
+                chain,
+                target_pool
+            });
+        }
+    }
+}
+
 #[derive(Accounts)]
 #[instruction(message: Vec<u8>)]
 pub struct OnIBCInvocation<'info> {
@@ -644,7 +738,8 @@ pub struct OnIBCInvocation<'info> {
     #[account()]    // ! TODO add seeds => polymerase receiver set to pda with no seeds?
     pub polymerase_authority: Signer<'info>,
     #[account(
-        constraint = interface_state_account.key().eq(&Pubkey::new(&message[33..65])) @ ErrorCode::InvalidInterfaceAccount, // ! The interface_state_account, derived from the provided swap_pool account, must match the target cross chain pool id present in the payload (i.e. verify the provided swap pool) 
+        // NOTE: packets too short to contain the target pool are let through, so that they are rejected by on_recv_packet with the 'Decode' fail reason
+        constraint = message.len() < PAYLOAD_HEADER_LENGTH || interface_state_account.key().eq(&Pubkey::new(&message[33..65])) @ ErrorCode::InvalidInterfaceAccount, // ! The interface_state_account, derived from the provided swap_pool account, must match the target cross chain pool id present in the payload (i.e. verify the provided swap pool) 
         constraint = interface_state_account.swap_pool == swap_pool.key() @ ErrorCode::InvalidSwapPoolAccount               // ! The swap_pool must match the one saved
     )]
     pub interface_state_account: Box<Account<'info, CrossChainSwapInterfaceState>>,    //TODO! must verify that it has been created using swap_pool
@@ -675,6 +770,171 @@ pub struct OnIBCInvocation<'info> {
 
 impl<'info> OnIBCInvocation<'info> {
 
+    // Counts the assets of the swap pool by reading its state directly, as the interface does not depend on the pool crates
+    pub fn get_pool_asset_count(&self) -> Result<usize> {
+
+        let swap_pool_data = self.swap_pool.try_borrow_data()?;
+
+        if swap_pool_data.len() < POOL_ASSETS_MINTS_OFFSET + 32*POOL_MAX_ASSETS {
+            return Err(error!(ErrorCode::InvalidSwapPoolAccount));
+        }
+
+        Ok(
+            swap_pool_data[POOL_ASSETS_MINTS_OFFSET..POOL_ASSETS_MINTS_OFFSET + 32*POOL_MAX_ASSETS]
+                .chunks(32)
+                .position(|mint| mint.iter().all(|byte| *byte == 0))   // Unused asset slots are set to the default pubkey
+                .unwrap_or(POOL_MAX_ASSETS)
+        )
+    }
+
+
+    pub fn process_recv_packet(
+        &self,
+        remaining_accounts: &[AccountInfo<'info>],
+        message: &[u8]
+    ) -> Result<()> {
+
+        // ! TODO:
+        // ! PolymeraseInterface authority verification in OnRecvPacket context
+        // !    ==> Only allow the authorized PolymeraseInterface authority holder to execute this function
+
+        // let chain //TODO
+
+        // Corrupt packets must error (rather than panic) while being decoded
+        if message.len() < PAYLOAD_HEADER_LENGTH {
+            return Err(error!(ErrorCode::InvalidPayloadLength));
+        }
+
+        let context: u8 = u8::from_be_bytes(message[..1].try_into().unwrap());
+        let source_pool: Pubkey = Pubkey::new(&message[1..33]);
+        let target_pool: Pubkey = Pubkey::new(&message[33..65]);
+
+        if self.interface_state_account.is_pool_blocklisted(&source_pool) {
+            return Err(error!(ErrorCode::PoolBlocklisted));
+        }
+
+        //TODO move to context?
+        // Check the provided SwapPool program is the expected one
+        if !self.swap_pool_program.key.eq(&self.swap_pool.owner) {
+            return Err(error!(ErrorCode::InvalidSwapPoolProgram));
+        }
+
+        //TODO!
+        // //TODO move to context?
+        // // Check the source chain + pool is valid, i.e. it is a connected and enabled pool connection
+        // if !self.connection_state_account.state {
+        //     return Err(error!(ErrorCode::SourcePoolNotConnected));
+        // }
+
+        match context {
+
+            CTX_ASSET_SWAP | CTX_ASSET_APPROX_SWAP => {
+                // Invoke in_swap of the target pool
+                /*
+                    Message Format
+                    0       context            : u8
+                    1-32    source_pool        : Pubkey
+                    33-64   target_pool        : Pubkey
+                    65-96   destination        : Pubkey
+                    97-128  units              : u256 (as 4 u64)
+                    129     target_asset_index : u8
+                    130-161 min_output         : u256 (as 4 u64)
+                    162-165 source_swap_id     : u32
+                */
+
+                if message.len() != ASSET_SWAP_PAYLOAD_LENGTH {
+                    return Err(error!(ErrorCode::InvalidPayloadLength));
+                }
+    
+                let units_x64: [u64; 4] = [                                         // TODO create helper function
+                    u64::from_be_bytes(message[97..105].try_into().unwrap()),
+                    u64::from_be_bytes(message[105..113].try_into().unwrap()),
+                    u64::from_be_bytes(message[113..121].try_into().unwrap()),
+                    u64::from_be_bytes(message[121..129].try_into().unwrap())
+                ];
+
+                self.interface_state_account.verify_units_per_packet(self.interface_state_account.key(), units_x64)?;
+
+
+                // Min output: for Solana, the maximum value allowed is u64
+                let min_output: u64 = u64::from_be_bytes(message[130..138].try_into().unwrap());
+
+                // Make sure that the rest of the 'min_output' value is zero
+                if !message[138..162].iter().all(|slice| slice == &0) {
+                    return Err(error!(ErrorCode::MinimumOutputExceedsMaxAllowed));
+                }
+
+
+                // ! VERY IMPORTANT verify provided output_asset_wallet matches the one specified in the message
+                if Pubkey::new(&message[65..97]).ne(&remaining_accounts[1].key()) {
+                    return Err(error!(ErrorCode::InvalidAssetWalletAccount));
+                }
+
+                // Make sure the target asset exists, rather than failing within the pool
+                if message[129] as usize >= self.get_pool_asset_count()? {
+                    return Err(error!(ErrorCode::InvalidTargetAssetIndex));
+                }
+
+
+                self.invoke_in_swap(
+                    message[129],
+                    units_x64,
+                    min_output,
+                    context == CTX_ASSET_APPROX_SWAP,
+                    remaining_accounts[0].to_account_info(),
+                    remaining_accounts[1].to_account_info(),
+                    remaining_accounts[2].to_account_info(),
+                    remaining_accounts[3].to_account_info(),
+                )?;
+    
+                Ok(())
+
+            },
+
+            CTX_LIQUIDITY_SWAP => {
+                // Invoke in_liquidity_swap of the target pool
+                /*
+                    Message Format
+                    0       context            : u8
+                    1-32    source_pool        : Pubkey
+                    33-64   target_pool        : Pubkey
+                    65-96   destination        : Pubkey
+                    97-128  units              : u256 (as 4 u64)
+                */
+
+                if message.len() != LIQUIDITY_SWAP_PAYLOAD_LENGTH {
+                    return Err(error!(ErrorCode::InvalidPayloadLength));
+                }
+    
+                // CPI - Arguments 
+                let liquidity_units_x64: [u64; 4] = [
+                    u64::from_be_bytes(message[97..105].try_into().unwrap()),
+                    u64::from_be_bytes(message[105..113].try_into().unwrap()),
+                    u64::from_be_bytes(message[113..121].try_into().unwrap()),
+                    u64::from_be_bytes(message[121..129].try_into().unwrap())
+                ];
+
+                self.interface_state_account.verify_units_per_packet(self.interface_state_account.key(), liquidity_units_x64)?;
+
+                // ! VERY IMPORTANT verify provided destination_pool_token_wallet matches the one specified in the message
+                if Pubkey::new(&message[65..97]).ne(&remaining_accounts[1].key()) {
+                    return Err(error!(ErrorCode::InvalidPoolTokenWalletAccount));
+                }
+
+                self.invoke_liquidity_in_swap(
+                    liquidity_units_x64,
+                    remaining_accounts[0].to_account_info(),
+                    remaining_accounts[1].to_account_info(),
+                    remaining_accounts[2].to_account_info()
+                )?;
+
+                Ok(())
+            },
+            _ => Err(error!(ErrorCode::InvalidContext))
+        }
+    }
+
+
     pub fn invoke_in_swap(
         &self,
         output_asset_index: u8,
@@ -876,6 +1136,7 @@ impl<'info> OnIBCResult<'info> {
     pub fn invoke_out_swap_ack(
         &self,
         source_swap_id: u32,
+        swap_pool_asset_wallet: AccountInfo<'info>,
         swap_escrow: AccountInfo<'info>,
         swap_escrow_rent_payer: AccountInfo<'info>
     ) -> ProgramResult {
@@ -904,6 +1165,11 @@ impl<'info> OnIBCResult<'info> {
                     is_signer: false,
                     is_writable: true,
                 },
+                AccountMeta {
+                    pubkey: swap_pool_asset_wallet.key(),
+                    is_signer: false,
+                    is_writable: false,
+                },
                 AccountMeta {
                     pubkey: self.interface_state_account.key(),
                     is_signer: true,
@@ -923,6 +1189,7 @@ impl<'info> OnIBCResult<'info> {
                 self.swap_pool.to_account_info(),
                 swap_escrow,
                 swap_escrow_rent_payer,
+                swap_pool_asset_wallet,
                 self.interface_state_account.to_account_info(),
                 self.system_program.to_account_info()
             ],
@@ -1042,22 +1309,72 @@ pub struct CrossChainSwapInterfaceState {
     pub swap_pool: Pubkey,
     pub swap_pool_authority: Pubkey,
     pub polymerase_endpoint_program: Pubkey,    //TODO Hardcode?
-    pub interface_state_account_bump: u8
+    pub interface_state_account_bump: u8,
+    pub max_units_per_packet: Option<[u64; 4]>, // Maximum units (x64) accepted by a single received packet. None for no cap.
+    pub default_timeout_blocks: u64,            // Timeout (in slots) applied to the sent packets
+    pub blocklisted_pools: [Pubkey; MAX_BLOCKLISTED_POOLS]  // Counterparty pools whose packets are rejected. Unused slots are set to the default pubkey.
 }
 
 impl CrossChainSwapInterfaceState {
-    pub const LEN: usize = 8 + 32*4 + 1;  // NOTE: includes discriminator
+    pub const LEN: usize = 8 + 32*4 + 1 + (1 + 8*4) + 8 + 32*MAX_BLOCKLISTED_POOLS;  // NOTE: includes discriminator
+
+    pub fn is_pool_blocklisted(&self, pool: &Pubkey) -> bool {
+        !pool.eq(&Pubkey::default()) && self.blocklisted_pools.contains(pool)
+    }
+
+    pub fn set_pool_blocklisted(&mut self, pool: Pubkey, blocklisted: bool) -> Result<()> {
+
+        if pool.eq(&Pubkey::default()) {
+            return Err(error!(ErrorCode::InvalidBlocklistedPool));
+        }
+
+        // Nothing to do if the pool is already in the requested state
+        if self.is_pool_blocklisted(&pool) == blocklisted {
+            return Ok(());
+        }
+
+        // Blocklist into the first unused slot, or clear the slot of the pool
+        let (from, to) = if blocklisted { (Pubkey::default(), pool) } else { (pool, Pubkey::default()) };
+
+        let slot = self.blocklisted_pools
+            .iter_mut()
+            .find(|blocklisted_pool| **blocklisted_pool == from)
+            .ok_or(error!(ErrorCode::BlocklistFull))?;
+
+        *slot = to;
+
+        Ok(())
+    }
+
+    // NOTE: The logs of failed transactions are kept, hence the emitted event can be observed even though the packet is rejected
+    pub fn verify_units_per_packet(&self, interface: Pubkey, units_x64: [u64; 4]) -> Result<()> {
+        if let Some(max_units_x64) = self.max_units_per_packet {
+            if U256(units_x64) > U256(max_units_x64) {
+                emit!(UnitsCapExceededEvent {
+                    interface,
+                    units_x64,
+                    max_units_x64
+                });
+                return Err(error!(ErrorCode::UnitsCapExceeded));
+            }
+        }
+
+        Ok(())
+    }
 }
 
 
 #[account]
 pub struct ConnectionState {
+    pub interface_state_account: Pubkey,    // NOTE: kept as the first field, used to filter the connections of an interface (memcmp at offset 8)
+    pub chain: u64,
+    pub pool: Pubkey,
     pub connected_interface_program: Pubkey,
     pub connection_state_account_bump: u8
 }
 
 impl ConnectionState {
-    pub const LEN: usize = 8 + 32*1 + 1;  // NOTE: includes discriminator
+    pub const LEN: usize = 8 + 32*3 + 8 + 1;  // NOTE: includes discriminator
 }
 
 
@@ -1096,9 +1413,90 @@ pub struct InLiquiditySwapArgs {
 
 
 
+// Query responses **************************************************************************************************************
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct VersionResponse {
+    pub contract: String,       // Crate name of the program
+    pub version: String         // Crate version of the program
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ConnectionStateResponse {
+    pub chain: u64,
+    pub pool: Pubkey,                           // Counterparty pool, as stored on the connection
+    pub connected_interface_program: Pubkey,    // Counterparty interface program
+    pub enabled: bool
+}
+
+
+
 // Events ***********************************************************************************************************************
 
-//TODO
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RecvPacketFailReason {
+    Decode,             // The payload could not be decoded
+    Validate,           // The payload or the provided accounts failed validation
+    Blocklisted,        // The source pool is blocklisted
+    UnitsCapExceeded,   // The received units exceed the interface cap (see UnitsCapExceededEvent)
+    Pool                // Any other failure (NOTE: errors raised by the invoked pool abort the transaction within the CPI, and are reported by the pool itself)
+}
+
+impl RecvPacketFailReason {
+    pub fn from_error(error: &Error) -> Self {
+        let error_code_number = match error {
+            Error::AnchorError(anchor_error) => anchor_error.error_code_number,
+            Error::ProgramError(_) => return RecvPacketFailReason::Pool
+        };
+
+        if [
+            ErrorCode::InvalidContext,
+            ErrorCode::InvalidPayloadLength,
+            ErrorCode::MinimumOutputExceedsMaxAllowed
+        ].into_iter().any(|error_code| u32::from(error_code) == error_code_number) {
+            return RecvPacketFailReason::Decode;
+        }
+
+        if u32::from(ErrorCode::PoolBlocklisted) == error_code_number {
+            return RecvPacketFailReason::Blocklisted;
+        }
+
+        if u32::from(ErrorCode::UnitsCapExceeded) == error_code_number {
+            return RecvPacketFailReason::UnitsCapExceeded;
+        }
+
+        if [
+            ErrorCode::InvalidSwapPoolProgram,
+            ErrorCode::InvalidAssetWalletAccount,
+            ErrorCode::InvalidPoolTokenWalletAccount,
+            ErrorCode::InvalidTargetAssetIndex
+        ].into_iter().any(|error_code| u32::from(error_code) == error_code_number) {
+            return RecvPacketFailReason::Validate;
+        }
+
+        RecvPacketFailReason::Pool
+    }
+}
+
+#[event]
+pub struct RecvPacketFailEvent {
+    interface: Pubkey,
+    fail_reason: RecvPacketFailReason
+}
+
+#[event]
+pub struct UnitsCapExceededEvent {
+    interface: Pubkey,
+    units_x64: [u64; 4],
+    max_units_x64: [u64; 4]
+}
+
+#[event]
+pub struct LoopbackPacketEvent {
+    interface: Pubkey,
+    chain: u64,
+    target_pool: Pubkey
+}
 
 
 // Errors ***********************************************************************************************************************
@@ -1127,4 +1525,18 @@ pub enum ErrorCode {
     InvalidPoolTokenWalletAccount,
     #[msg("The specified minimum output exceeds the allowed one by the chain implementation.")]
     MinimumOutputExceedsMaxAllowed,
+    #[msg("The received units exceed the maximum allowed per packet.")]
+    UnitsCapExceeded,
+    #[msg("The received payload length does not match the expected one for its context.")]
+    InvalidPayloadLength,
+    #[msg("The packet timeout is outside of the allowed bounds.")]
+    InvalidTimeout,
+    #[msg("The target asset index is out of the range of the pool assets.")]
+    InvalidTargetAssetIndex,
+    #[msg("The source pool is blocklisted.")]
+    PoolBlocklisted,
+    #[msg("The maximum number of blocklisted pools has been reached.")]
+    BlocklistFull,
+    #[msg("The default pubkey cannot be blocklisted.")]
+    InvalidBlocklistedPool,
 }
\ No newline at end of file
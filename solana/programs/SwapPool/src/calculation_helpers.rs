@@ -57,14 +57,20 @@ pub fn in_swap(
 ) -> Result<U256> {
     // Solves the following integral for 'y'
     // int_{Bt-y}^{Bt} WB/w dW
+    //
+    // NOTE: Units too large to be represented by the fixed point math (U/WB >= 41 for the exact formula) are rejected
+    // with UnitsExceedPool rather than clamped to the pool balance.
 
     if approx {
-        let units_times_ln2_x64 = mul_x64(units_x64, LN2_X64).unwrap();
+        let units_times_ln2_x64 = mul_x64(units_x64, LN2_X64)
+            .map_err(|_| error!(IntegralCalculationErrorCode::UnitsExceedPool))?;
 
         return Ok(
             div_x64(
-                target_asset_balance.checked_mul(units_times_ln2_x64).unwrap(),
-                target_asset_weight.shl(64).checked_add(units_times_ln2_x64).unwrap()
+                target_asset_balance.checked_mul(units_times_ln2_x64)
+                    .ok_or(error!(IntegralCalculationErrorCode::UnitsExceedPool))?,
+                target_asset_weight.shl(64).checked_add(units_times_ln2_x64)
+                    .ok_or(error!(IntegralCalculationErrorCode::UnitsExceedPool))?
             ).unwrap()
         )
     }
@@ -72,11 +78,23 @@ pub fn in_swap(
     // Bt * (1 - exp(-U/WB))
     Ok(target_asset_balance.checked_mul(
         ONE_X64.checked_sub(
-            inv_pow2_x64(units_x64.checked_div(target_asset_weight).unwrap()).unwrap()
+            inv_pow2_x64(units_x64.checked_div(target_asset_weight).unwrap())
+                .map_err(|_| error!(IntegralCalculationErrorCode::UnitsExceedPool))?
         ).unwrap()
     ).unwrap().shr(64u8))
 }
 
+// Derivative of in_swap with respect to the units at U = 0, i.e. the output received per unit (x64) by an infinitesimal
+// incoming swap. It bounds in_swap(U)/U from above, and the two converge as U shrinks (for both the exact and the approximate
+// formula, which share the same derivative at U = 0).
+pub fn marginal_in_swap_x64(
+    target_asset_balance: U256,     // Bt
+    target_asset_weight: U256       // WB
+) -> Result<U256> {
+    // Bt · ln(2) / WB
+    Ok(target_asset_balance.checked_mul(LN2_X64).unwrap().checked_div(target_asset_weight).unwrap())
+}
+
 pub fn full_swap(
     input: U256,
     source_asset_balance: U256,
@@ -116,10 +134,37 @@ pub fn full_swap(
 }
 
 
+// Deviation (in bps) of the execution price output/input from the marginal (spot) price (WA·Bt)/(WB·At)
+pub fn calc_price_impact_bps(
+    input: U256,
+    output: U256,
+    source_asset_balance: U256,     // At
+    source_asset_weight: U256,      // WA
+    target_asset_balance: U256,     // Bt
+    target_asset_weight: U256       // WB
+) -> Result<u64> {
+
+    if input.is_zero() { return Ok(0) }
+
+    // output/input / ((WA·Bt)/(WB·At)), in bps
+    let execution_to_spot_bps = U256::from(10_000u64).checked_mul(output).unwrap()
+        .checked_mul(target_asset_weight).unwrap()
+        .checked_mul(source_asset_balance).unwrap()
+        .checked_div(
+            input.checked_mul(source_asset_weight).unwrap().checked_mul(target_asset_balance).unwrap()
+        ).ok_or(error!(IntegralCalculationErrorCode::ArithmeticError))?;   // Depleted target asset
+
+    // The execution price can round above the spot price for very small swaps
+    Ok(10_000u64.saturating_sub(execution_to_spot_bps.min(U256::from(10_000u64)).as_u64()))
+}
+
+
 #[error_code]
 pub enum IntegralCalculationErrorCode {
     #[msg("Arithmetic Error. Possible overflow/underflow.")]
     ArithmeticError,
+    #[msg("The units exceed what the pool can pay out.")]
+    UnitsExceedPool,
 }
 
 
@@ -1,13 +1,16 @@
 use std::convert::TryInto;
 
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
 
 use ibc_interface;
 use ibc_interface::cpi::accounts as interface_accounts;
 use ibc_interface::program::IbcInterface;
 
 use shared_lib::u256::U256;
+use shared_lib::fee_math::compute_fee;
+use shared_lib::mul_div::mul_div;
+use shared_lib::asset_iteration::for_each_asset;
 use token_utils::create_pda_token_account;
 
 pub mod calculation_helpers;
@@ -19,10 +22,39 @@ const NUMASSETS: usize = 3;
 
 const DECAYRATE: u64 = 60*60*24;
 
+// Time after initialization after which the setup_master can no longer perform setup operations, even if the setup
+// has not been explicitly finished.
+const SETUP_WINDOW: u64 = 60*60*24*7;
+
 const POOL_ASSET_WALLET_SEED : &[u8] = b"poolAsset";
 const POOL_TOKEN_MINT_SEED   : &[u8] = b"poolMint";
 const POOL_AUTHORITY_SEED    : &[u8] = b"poolAuth";
 const POOL_ESCROW_SEED       : &[u8] = b"poolEscrow";
+const POOL_LOCKED_TOKENS_SEED: &[u8] = b"poolLocked";
+
+// Pool tokens minted on initialization. Part of them are permanently locked, so that the pool token supply can never be
+// brought down to a few units (which would allow to inflate the pool token price and steal from subsequent depositors by
+// rounding their deposits down).
+const INITIAL_POOL_TOKENS    : u64   = 1000000;   // TODO set value (cannot be set equal to EVM implementation, as that is too large)
+const LOCKED_POOL_TOKENS     : u64   = 1000;
+
+const MAX_CONNECTIONS_PER_BATCH: usize = 8;
+
+const SUPPLY_CHECKPOINTS: usize = 16;     // Number of pool token supply checkpoints kept (older ones are pruned)
+
+const DEPTH_SEARCH_MAX_BALANCE_MULTIPLE: u64 = 1 << 16;
+
+const VAULT_TYPE             : &str  = "volatile";
+const VAULT_SCHEMA_VERSION   : u32   = 1;
+
+// Lists the instructions exposed by this pool type on top of the ones common to all pool types. Every entry must name an
+// instruction handler of the program, so that the reported capabilities cannot drift from the exposed instructions.
+macro_rules! vault_capabilities {
+    ($($instruction:ident),*) => {{
+        $( let _ = $instruction; )*
+        vec![$(stringify!($instruction).to_string()),*]
+    }};
+}
 
 #[program]
 pub mod swap_pool {
@@ -42,6 +74,8 @@ pub mod swap_pool {
 
         // ! Save the pubkey of setup_master to verify setup instructions (only allow setup_master to call setup instructions)
         ctx.accounts.swap_pool_state_account.setup_master = ctx.accounts.setup_master.key();
+        let current_timestamp: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
+        ctx.accounts.swap_pool_state_account.setup_deadline = current_timestamp + SETUP_WINDOW;
         ctx.accounts.swap_pool_state_account.dao_authority = ctx.accounts.dao_authority.key();
         ctx.accounts.swap_pool_state_account.authority_bump = ctx.bumps.get("swap_pool_authority").unwrap().to_owned();
         ctx.accounts.swap_pool_state_account.token_mint_bump = ctx.bumps.get("swap_pool_token_mint").unwrap().to_owned();
@@ -106,8 +140,13 @@ pub mod swap_pool {
 
         // Mint pool tokens for the depositor
         ctx.accounts.mint_pool_tokens_for_depositor(
-            1000000,    // TODO set value (cannot be set equal to EVM implementation, as that is too large)
+            INITIAL_POOL_TOKENS - LOCKED_POOL_TOKENS
         ).unwrap();
+
+        // Lock the remaining pool tokens
+        ctx.accounts.lock_pool_tokens(LOCKED_POOL_TOKENS).unwrap();
+
+        ctx.accounts.swap_pool_state_account.checkpoint_pool_token_supply(Clock::get()?.slot, INITIAL_POOL_TOKENS);
         
         Ok(())
     }
@@ -151,6 +190,55 @@ pub mod swap_pool {
         Ok(())
     }
 
+    // All the connections are created within the same instruction: if any of them fails, none is created.
+    pub fn create_connections<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CreateConnections<'info>>,
+        connections: Vec<ConnectionUpdate>
+    ) -> Result<()> {
+
+        // 1 Account given per connection:
+        //      - Connection state account (mut)
+        if connections.len() > MAX_CONNECTIONS_PER_BATCH {
+            return Err(error!(ErrorCode::TooManyConnections));
+        }
+
+        if ctx.remaining_accounts.len() != connections.len() {
+            return Err(error!(ErrorCode::InvalidRemainingAccountsCount));
+        }
+
+        let signer: &[&[&[u8]]] = &[&[
+            ctx.accounts.swap_pool_state_account.to_account_info().key.as_ref(),
+            &POOL_AUTHORITY_SEED[..],
+            &[ctx.accounts.swap_pool_state_account.authority_bump]   // PDA bump
+        ]];
+
+        for (connection, connection_state_account) in connections.iter().zip(ctx.remaining_accounts) {
+
+            let ccsi_swap_accounts = interface_accounts::CreateConnection {
+                swap_pool_authority: ctx.accounts.swap_pool_authority.to_account_info(),
+                rent_payer: ctx.accounts.rent_payer.to_account_info(),
+                interface_state_account: ctx.accounts.interface_state_account.to_account_info(),
+                connection_state_account: connection_state_account.to_owned(),
+                system_program: ctx.accounts.system_program.to_account_info()
+            };
+
+            let ccsi_swap_ctx = CpiContext::new_with_signer(
+                ctx.accounts.ibc_interface_program.to_account_info(),
+                ccsi_swap_accounts,
+                signer
+            );
+
+            ibc_interface::cpi::create_connection(
+                ccsi_swap_ctx,
+                connection.chain,
+                connection.pool,
+                connection.interface_program
+            )?;
+        }
+
+        Ok(())
+    }
+
     pub fn link_ibc_interface(
         ctx: Context<LinkIBCInterface>,
         ibc_interface: Pubkey
@@ -245,6 +333,12 @@ pub mod swap_pool {
                 asset_eq_balance
             )?;
 
+            swap_pool_state_account.verify_deposit_cap(asset_index, swap_pool_asset_wallet_balance, asset_deposit_amount)?;
+
+            // Accumulate the price before the deposit
+            let available_balance = swap_pool_asset_wallet_balance.checked_sub(swap_pool_state_account.escrowed_assets[asset_index]).unwrap();
+            swap_pool_state_account.update_price_accumulator(asset_index, available_balance, current_timestamp)?;
+
             // Update asset eq balance
             swap_pool_state_account.pool_assets_eq_balances[asset_index] = asset_eq_balance.checked_add(pool_tokens_for_asset).unwrap();
 
@@ -280,6 +374,10 @@ pub mod swap_pool {
             ctx.accounts.token_program.to_account_info()
         );
 
+        // NOTE: the loaded mint data is not updated by the mint operation
+        let pool_token_supply = ctx.accounts.swap_pool_token_mint.supply.checked_add(pool_tokens_amount).unwrap();
+        ctx.accounts.swap_pool_state_account.checkpoint_pool_token_supply(Clock::get()?.slot, pool_token_supply);
+
         emit!(DepositEvent {
             swap_pool: ctx.accounts.swap_pool_state_account.key(),
 
@@ -295,46 +393,59 @@ pub mod swap_pool {
 
     pub fn withdraw<'a, 'b, 'c, 'info>(
         ctx: Context<'a, 'b, 'c, 'info, Withdraw<'info>>,
-        pool_tokens_amount: u64
+        pool_tokens_amount: u64,
+        to: Option<Pubkey>          // If specified, the withdrawn assets must be sent to token wallets owned by this account
     ) -> Result<()> {
+        withdraw_assets(ctx, pool_tokens_amount, to, [0; NUMASSETS])
+    }
 
-        // Update the liqudity security limit. Since the limit is based on the current totalSupply, changing the totalSupply
-        // downwards by withdrawing changes the limit.
-        let current_timestamp: u64 = Clock::get().unwrap().unix_timestamp.try_into().unwrap();
-        ctx.accounts.swap_pool_state_account.update_liquidity_units_inflow(
-            0,
-            ctx.accounts.swap_pool_token_mint.supply,
-            current_timestamp
-        )?;
+    // Withdraws the same share of every pool asset balance (i.e. no price impact), failing if any of the withdrawn amounts
+    // is below the given minimum.
+    pub fn withdraw_balanced<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, Withdraw<'info>>,
+        pool_tokens_amount: u64,
+        min_amounts: [u64; NUMASSETS]
+    ) -> Result<()> {
+        withdraw_assets(ctx, pool_tokens_amount, None, min_amounts)
+    }
 
-        // Burn pool tokens from the withdrawer wallet.
-        ctx.accounts.burn_pool_tokens_of_withdrawer(pool_tokens_amount)?;
+    pub fn donate<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, Donate<'info>>,
+        amounts: [u64; NUMASSETS]
+    ) -> Result<()> {
+
+        // NOTE: No pool tokens are minted and the eq balances are left untouched, hence the donated assets are shared
+        // among the existing pool token holders (i.e. the value of each pool token increases).
 
-        let mut withdrawn_amounts: [u64; NUMASSETS] = [0; NUMASSETS];
+        let current_timestamp: u64 = Clock::get().unwrap().unix_timestamp.try_into().unwrap();
 
         let swap_pool_state_account = &mut ctx.accounts.swap_pool_state_account;
 
         let pool_assets_mints: [Pubkey; NUMASSETS] = swap_pool_state_account.pool_assets_mints.clone();
 
-        let mut withdrawer_asset_wallets: [Pubkey; NUMASSETS] = [Pubkey::default(); NUMASSETS];
+        let asset_count = pool_assets_mints.iter().position(|mint| *mint == Pubkey::default()).unwrap_or(NUMASSETS);
 
-        let initial_pool_tokens_supply = ctx.accounts.swap_pool_token_mint.supply;    // Theoretically, this is not needed, as the 'burn' operation does not update the data that has been loaded from the account, but this is here for clarity
-        
-        // 3 Accounts given per withdrawn asset:
+        // Make sure no amount is given for an asset that is not contained in the pool
+        if amounts[asset_count..].iter().any(|amount| *amount != 0) {
+            return Err(error!(ErrorCode::InvalidDonationAmounts));
+        }
+
+        // Verify the count of passed remaining accounts
+        // 3 Accounts given per donated asset:
         //      - Asset mint
         //      - Swap pool asset wallet
-        //      - Depositor asset wallet
-        for asset_index in 0..NUMASSETS {
+        //      - Donor asset wallet (owned by the donor, verified by the token program on transfer)
+        if ctx.remaining_accounts.len() != 3*asset_count {
+            return Err(error!(ErrorCode::InvalidRemainingAccountsCount));
+        }
 
-            if swap_pool_state_account.pool_assets_mints[asset_index].eq(&Pubkey::default()) {
-                break;
-            }
+        let mut donor_asset_wallets: [Pubkey; NUMASSETS] = [Pubkey::default(); NUMASSETS];
 
-            // Verify the withdrawer_asset_wallet and the swap_pool_asset_wallet accounts
-            // ! TODO VERY IMPORTANT, THIS VERIFICATION HAS TO BE REVISED
-            let asset_mint              = &ctx.remaining_accounts[asset_index*3];    // Mint account verified by token account creation cpi // ! TODO verify
-            let swap_pool_asset_wallet  = &ctx.remaining_accounts[asset_index*3+1];  // Verified in 'create_swap_pool_asset_wallet' step
-            let withdrawer_asset_wallet = &ctx.remaining_accounts[asset_index*3+2];  // No need to verify, assets taken from here
+        for asset_index in 0..asset_count {
+
+            let asset_mint             = &ctx.remaining_accounts[asset_index*3];
+            let swap_pool_asset_wallet = &ctx.remaining_accounts[asset_index*3+1];
+            let donor_asset_wallet     = &ctx.remaining_accounts[asset_index*3+2];
             
             // Verify the asset mint
             if asset_mint.key().ne(&pool_assets_mints[asset_index]) {
@@ -354,62 +465,147 @@ pub mod swap_pool {
             if swap_pool_asset_wallet.key() != expected_swap_pool_asset_wallet {
                 return Err(error!(ErrorCode::InvalidSwapPoolAssetWalletAccount));
             }
-        
-            let swap_pool_asset_wallet_balance = TokenAccount::try_deserialize(&mut &swap_pool_asset_wallet.try_borrow_data()?[..])?.amount;
 
-            // Compute the asset withdraw amount
-            let asset_eq_balance = swap_pool_state_account.pool_assets_eq_balances[asset_index];
-            let pool_tokens_for_asset = pool_tokens_amount
-                .checked_mul(asset_eq_balance).unwrap()
-                .checked_div(initial_pool_tokens_supply).unwrap();
-            
-            let asset_withdraw_amount = calculation_helpers::calc_asset_amount_for_pool_tokens(
-                pool_tokens_for_asset,
-                swap_pool_asset_wallet_balance
-                    .checked_sub(swap_pool_state_account.escrowed_assets[asset_index]).unwrap(),         // Escrowed tokens ARE subtracted from the total balance => withdrawals should return less
-                asset_eq_balance
-            )?;
+            donor_asset_wallets[asset_index] = donor_asset_wallet.key();
 
-            // Update asset eq balance
-            swap_pool_state_account.pool_assets_eq_balances[asset_index] = asset_eq_balance.checked_sub(pool_tokens_for_asset).unwrap();
-        
-            token_utils::transfer_tokens_using_pda_authority(
-                asset_withdraw_amount,
-                swap_pool_asset_wallet.to_account_info(),
-                withdrawer_asset_wallet.to_account_info(),
-                ctx.accounts.swap_pool_authority.to_account_info(),
-                &[
-                    &swap_pool_state_account.key().to_bytes(), // SwapPool state account
-                    &POOL_AUTHORITY_SEED[..],                  // Pool authority seed
-                    &[swap_pool_state_account.authority_bump]  // PDA bump
-                ],
-                ctx.accounts.token_program.to_account_info()
-            );
-    
-            // Save data for logging
-            withdrawer_asset_wallets[asset_index] = withdrawer_asset_wallet.key();
-            withdrawn_amounts[asset_index] = asset_withdraw_amount;
+            if amounts[asset_index] == 0 {
+                continue;
+            }
+
+            // Accumulate the price before the donation
+            let available_balance = TokenAccount::try_deserialize(&mut &swap_pool_asset_wallet.try_borrow_data()?[..])?.amount
+                .checked_sub(swap_pool_state_account.escrowed_assets[asset_index]).unwrap();
+            swap_pool_state_account.update_price_accumulator(asset_index, available_balance, current_timestamp)?;
+
+            // Transfer with the donor authority rather than with the pool authority, so that the assets can only be taken from
+            // wallets owned by the signer of the donation (and not from any wallet with an outstanding approval to the pool)
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: donor_asset_wallet.to_owned(),
+                        to: swap_pool_asset_wallet.to_owned(),
+                        authority: ctx.accounts.donor.to_account_info()
+                    }
+                ),
+                amounts[asset_index]
+            )?;
         }
 
-        emit!(WithdrawEvent {
+        emit!(DonateEvent {
             swap_pool: ctx.accounts.swap_pool_state_account.key(),
-        
-            withdrawn_asset_amounts: withdrawn_amounts,
-            withdrawer_asset_wallets: withdrawer_asset_wallets,
-        
-            burnt_pool_token_amount: pool_tokens_amount,
-            withdrawer_pool_token_wallet: ctx.accounts.withdrawer_pool_token_wallet.key(),
+
+            donated_asset_amounts: amounts,
+            donor_asset_wallets: donor_asset_wallets
+        });
+
+        Ok(())
+    }
+
+    pub fn set_min_swap_amount(
+        ctx: Context<SetMinSwapAmount>,
+        asset: Pubkey,
+        min_swap_amount: u64            // 0 for no minimum
+    ) -> Result<()> {
+
+        let asset_index = ctx.accounts.swap_pool_state_account.get_asset_index(&asset)
+            .ok_or(error!(ErrorCode::InvalidAssetMintAccount))?;
+
+        ctx.accounts.swap_pool_state_account.min_swap_amounts[asset_index] = min_swap_amount;
+
+        Ok(())
+    }
+
+    pub fn set_max_asset_outflow(
+        ctx: Context<SetMaxAssetOutflow>,
+        asset: Pubkey,
+        max_asset_outflow: u64          // 0 for no limit
+    ) -> Result<()> {
+
+        let asset_index = ctx.accounts.swap_pool_state_account.get_asset_index(&asset)
+            .ok_or(error!(ErrorCode::InvalidAssetMintAccount))?;
+
+        ctx.accounts.swap_pool_state_account.max_asset_outflows[asset_index] = max_asset_outflow;
+
+        Ok(())
+    }
+
+    pub fn set_deposit_cap(
+        ctx: Context<SetDepositCap>,
+        asset: Pubkey,
+        deposit_cap: u64                // 0 for no cap
+    ) -> Result<()> {
+
+        let asset_index = ctx.accounts.swap_pool_state_account.get_asset_index(&asset)
+            .ok_or(error!(ErrorCode::InvalidAssetMintAccount))?;
+
+        ctx.accounts.swap_pool_state_account.deposit_caps[asset_index] = deposit_cap;
+
+        Ok(())
+    }
+
+    pub fn set_dao_authority(ctx: Context<SetDaoAuthority>) -> Result<()> {
+
+        // ! The new dao authority must sign the transaction (see the SetDaoAuthority context), so that the pool can never be
+        // ! handed over to an address that does not acknowledge it
+        let old_dao_authority = ctx.accounts.dao_authority.key();
+        let new_dao_authority = ctx.accounts.new_dao_authority.key();
+
+        ctx.accounts.swap_pool_state_account.dao_authority = new_dao_authority;
+
+        emit!(SetDaoAuthorityEvent {
+            swap_pool: ctx.accounts.swap_pool_state_account.key(),
+            old_dao_authority,
+            new_dao_authority
+        });
+
+        Ok(())
+    }
+
+    pub fn rescue_token(ctx: Context<RescueToken>) -> Result<()> {
+
+        // Transfer the whole balance of the wallet (the wallet mint cannot be a pool asset, see the RescueToken context)
+        let amount = ctx.accounts.rescued_token_wallet.amount;
+
+        token_utils::transfer_tokens_using_pda_authority(  //TODO use transfer_checked?
+            amount,
+            ctx.accounts.rescued_token_wallet.to_account_info(),
+            ctx.accounts.recipient_wallet.to_account_info(),
+            ctx.accounts.swap_pool_authority.to_account_info(),
+            &[
+                &ctx.accounts.swap_pool_state_account.key().to_bytes(),  // SwapPool state account
+                &POOL_AUTHORITY_SEED[..],                                // Pool authority seed
+                &[ctx.accounts.swap_pool_state_account.authority_bump]   // PDA bump
+            ],
+            ctx.accounts.token_program.to_account_info()
+        );
+
+        emit!(RescueTokenEvent {
+            swap_pool: ctx.accounts.swap_pool_state_account.key(),
+            rescued_token_mint: ctx.accounts.rescued_token_wallet.mint,
+            recipient_wallet: ctx.accounts.recipient_wallet.key(),
+            amount
         });
 
         Ok(())
     }
 
-    pub fn local_swap(ctx: Context<LocalSwap>, amount: u64, min_yield: u64, approx: bool) -> Result<()> {
+    pub fn local_swap(
+        ctx: Context<LocalSwap>,
+        amount: u64,
+        min_yield: u64,
+        approx: bool,
+        deadline: Option<u64>       // Timestamp after which the swap is rejected (None for no deadline)
+    ) -> Result<()> {
+
+        verify_deadline(deadline)?;
 
         let from_asset_index = ctx.accounts.swap_pool_state_account.get_asset_index(
             &ctx.accounts.input_asset_mint.key()
         ).unwrap();
 
+        ctx.accounts.swap_pool_state_account.verify_min_swap_amount(from_asset_index, amount)?;
+
         let to_asset_index = ctx.accounts.swap_pool_state_account.get_asset_index(
             &ctx.accounts.output_asset_mint.key()
         ).unwrap();
@@ -429,6 +625,18 @@ pub mod swap_pool {
 
         if out < min_yield { return Err(error!(ErrorCode::SwapMinYieldNotFulfilled))}
 
+        ctx.accounts.swap_pool_state_account.add_volume_in(from_asset_index, amount);
+        ctx.accounts.swap_pool_state_account.add_volume_out(to_asset_index, out);
+
+        // Accumulate the prices before the swap
+        let current_timestamp: u64 = Clock::get().unwrap().unix_timestamp.try_into().unwrap();
+        let input_asset_balance = ctx.accounts.swap_pool_input_asset_wallet.amount;
+        let output_asset_balance = ctx.accounts.swap_pool_output_asset_wallet.amount.checked_sub(
+            ctx.accounts.swap_pool_state_account.escrowed_assets[to_asset_index]
+        ).unwrap();
+        ctx.accounts.swap_pool_state_account.update_price_accumulator(from_asset_index, input_asset_balance, current_timestamp)?;
+        ctx.accounts.swap_pool_state_account.update_price_accumulator(to_asset_index, output_asset_balance, current_timestamp)?;
+
         // Transfer input assets to the pool
         token_utils::transfer_tokens_using_pda_authority(  //TODO use transfer_checked?
             amount,
@@ -484,15 +692,19 @@ pub mod swap_pool {
         amount: u64,
         min_output: [u64; 4],
         escrow_nonce: u32,
-        fallback_wallet: Pubkey,
         approx_from: bool,
-        approx_to: bool
+        approx_to: bool,
+        deadline: Option<u64>       // Timestamp after which the swap is rejected (None for no deadline)
     ) -> Result<()> {
 
+        verify_deadline(deadline)?;
+
         let from_asset_index = ctx.accounts.swap_pool_state_account.get_asset_index(
             &ctx.accounts.input_asset_mint.key()
         ).unwrap();
 
+        ctx.accounts.swap_pool_state_account.verify_min_swap_amount(from_asset_index, amount)?;
+
         let units_x64 = calculation_helpers::out_swap_x64(
             U256::from(amount),
             U256::from(ctx.accounts.swap_pool_input_asset_wallet.amount),
@@ -556,12 +768,25 @@ pub mod swap_pool {
         // Escrow the tokens
         ctx.accounts.swap_pool_state_account.escrowed_assets[from_asset_index] =
             ctx.accounts.swap_pool_state_account.escrowed_assets[from_asset_index].checked_add(amount).unwrap();
+        ctx.accounts.swap_pool_state_account.open_escrows = ctx.accounts.swap_pool_state_account.open_escrows.checked_add(1).unwrap();
+
+        ctx.accounts.swap_pool_state_account.add_volume_in(from_asset_index, amount);
+
+        // Accumulate the price before the swap
+        let current_timestamp: u64 = Clock::get().unwrap().unix_timestamp.try_into().unwrap();
+        let input_asset_balance = ctx.accounts.swap_pool_input_asset_wallet.amount;
+        ctx.accounts.swap_pool_state_account.update_price_accumulator(from_asset_index, input_asset_balance, current_timestamp)?;
 
         ctx.accounts.swap_escrow.amount                 = amount;
         ctx.accounts.swap_escrow.asset_index            = from_asset_index as u8;
-        ctx.accounts.swap_escrow.fallback_wallet        = fallback_wallet;
+        ctx.accounts.swap_escrow.fallback_wallet        = ctx.accounts.fallback_wallet.key();
         ctx.accounts.swap_escrow.swap_escrow_rent_payer = ctx.accounts.swap_escrow_rent_payer.key();
         ctx.accounts.swap_escrow.bump                   = ctx.bumps.get("swap_escrow").unwrap().to_owned();
+        ctx.accounts.swap_escrow.target_chain           = chain;
+        ctx.accounts.swap_escrow.target_pool            = target_pool;
+        ctx.accounts.swap_escrow.to_asset_index         = to_asset_index;
+        ctx.accounts.swap_escrow.target_withdrawer      = destination;
+        ctx.accounts.swap_escrow.units_x64              = units_x64.0;
 
         // Incoming swaps are subtracted from the net pool unit flow. It is assumed that if the router is fraudulent, 
         // no one will execute a trade. Hence, if people swap into the pool, it is expected that there is exactly that 
@@ -575,6 +800,18 @@ pub mod swap_pool {
             ctx.accounts.swap_pool_state_account.current_units_inflow_x64 = [0, 0, 0, 0];            
         }
 
+        emit!(SwapEscrowCreatedEvent {
+            swap_pool: ctx.accounts.swap_pool_state_account.key(),
+            escrow_nonce,
+
+            asset_mint: ctx.accounts.input_asset_mint.key(),
+            amount,
+            fallback_wallet: ctx.accounts.fallback_wallet.key(),
+
+            target_chain: chain,
+            target_withdrawer: destination
+        });
+
         emit!(OutSwapEvent {
             swap_pool: ctx.accounts.swap_pool_state_account.key(),
             target_pool: target_pool,
@@ -603,14 +840,28 @@ pub mod swap_pool {
         let escrowed_amount = ctx.accounts.swap_escrow.amount;
         let escrowed_asset_index = ctx.accounts.swap_escrow.asset_index as usize;
 
+        // Accumulate the price before the escrow is released
+        let current_timestamp: u64 = Clock::get().unwrap().unix_timestamp.try_into().unwrap();
+        let available_balance = ctx.accounts.swap_pool_asset_wallet.amount.checked_sub(
+            ctx.accounts.swap_pool_state_account.escrowed_assets[escrowed_asset_index]
+        ).unwrap();
+        ctx.accounts.swap_pool_state_account.update_price_accumulator(escrowed_asset_index, available_balance, current_timestamp)?;
+
         ctx.accounts.swap_pool_state_account.escrowed_assets[escrowed_asset_index] = 
             ctx.accounts.swap_pool_state_account.escrowed_assets[escrowed_asset_index].checked_sub(escrowed_amount).unwrap();
+        ctx.accounts.swap_pool_state_account.open_escrows = ctx.accounts.swap_pool_state_account.open_escrows.checked_sub(1).unwrap();
 
         emit!(OutSwapAckEvent {
             swap_pool: ctx.accounts.swap_pool_state_account.key(),
             escrow_nonce
         });
 
+        emit!(SwapEscrowResolvedEvent {
+            swap_pool: ctx.accounts.swap_pool_state_account.key(),
+            escrow_nonce,
+            outcome: SwapEscrowOutcome::Acknowledged
+        });
+
         Ok(())
     }
 
@@ -622,8 +873,16 @@ pub mod swap_pool {
         let escrowed_amount = ctx.accounts.swap_escrow.amount;
         let escrowed_asset_index = ctx.accounts.swap_escrow.asset_index as usize;
 
+        // Accumulate the price before the escrow is released
+        let current_timestamp: u64 = Clock::get().unwrap().unix_timestamp.try_into().unwrap();
+        let available_balance = ctx.accounts.swap_pool_asset_wallet.amount.checked_sub(
+            ctx.accounts.swap_pool_state_account.escrowed_assets[escrowed_asset_index]
+        ).unwrap();
+        ctx.accounts.swap_pool_state_account.update_price_accumulator(escrowed_asset_index, available_balance, current_timestamp)?;
+
         ctx.accounts.swap_pool_state_account.escrowed_assets[escrowed_asset_index] = 
             ctx.accounts.swap_pool_state_account.escrowed_assets[escrowed_asset_index].checked_sub(escrowed_amount).unwrap();
+        ctx.accounts.swap_pool_state_account.open_escrows = ctx.accounts.swap_pool_state_account.open_escrows.checked_sub(1).unwrap();
         
         // Transfer tokens to fallback_wallet
         token_utils::transfer_tokens_using_pda_authority(  //TODO use transfer_checked?
@@ -644,6 +903,12 @@ pub mod swap_pool {
             escrow_nonce
         });
 
+        emit!(SwapEscrowResolvedEvent {
+            swap_pool: ctx.accounts.swap_pool_state_account.key(),
+            escrow_nonce,
+            outcome: SwapEscrowOutcome::TimedOut
+        });
+
         Ok(())
     }
 
@@ -680,6 +945,16 @@ pub mod swap_pool {
         if output_balance < min_output {
             return Err(error!(ErrorCode::SwapMinYieldNotFulfilled))
         }
+
+        ctx.accounts.swap_pool_state_account.update_asset_outflow(to_asset_index, output_balance, current_timestamp)?;
+
+        // Accumulate the price before the swap
+        let output_asset_balance = ctx.accounts.swap_pool_output_asset_wallet.amount.checked_sub(
+            ctx.accounts.swap_pool_state_account.escrowed_assets[to_asset_index]
+        ).unwrap();
+        ctx.accounts.swap_pool_state_account.update_price_accumulator(to_asset_index, output_asset_balance, current_timestamp)?;
+
+        ctx.accounts.swap_pool_state_account.add_volume_out(to_asset_index, output_balance);
         //TODO! unitsDiff       line 888
 
         // Transfer output assets to the user
@@ -719,9 +994,15 @@ pub mod swap_pool {
         chain: u64,
         target_pool: Pubkey,
         destination: Pubkey,
-        pool_tokens_amount: u64
+        pool_tokens_amount: u64,
+        deadline: Option<u64>       // Timestamp after which the swap is rejected (None for no deadline)
     ) -> Result<()> {
 
+        verify_deadline(deadline)?;
+
+        // NOTE: Outgoing liquidity swaps only burn pool tokens, the pool asset balances (and hence the asset prices) are left untouched.
+        // The price accumulators are therefore not advanced (advancing them would not change their value at any later time).
+
         let swap_pool_state_account = &mut ctx.accounts.swap_pool_state_account;
 
         // Compute the total amount of liquidity units being transferred
@@ -754,6 +1035,10 @@ pub mod swap_pool {
         // Burn pool tokens
         ctx.accounts.burn_pool_tokens_of_sender(pool_tokens_amount)?;
 
+        // NOTE: the loaded mint data is not updated by the burn operation
+        let pool_token_supply = ctx.accounts.swap_pool_token_mint.supply.checked_sub(pool_tokens_amount).unwrap();
+        ctx.accounts.swap_pool_state_account.checkpoint_pool_token_supply(Clock::get()?.slot, pool_token_supply);
+
         // Perform cross chain swap
         let ccsi_program = ctx.accounts.ibc_interface_program.to_account_info(); // Check is the same as the stored program?
         let ccsi_swap_accounts = interface_accounts::CrossChainSwap {
@@ -818,6 +1103,9 @@ pub mod swap_pool {
         liquidity_units_x64: [u64; 4]
     ) -> Result<()> {
 
+        // NOTE: Incoming liquidity swaps only mint pool tokens, the pool asset balances (and hence the asset prices) are left untouched.
+        // The price accumulators are therefore not advanced (advancing them would not change their value at any later time).
+
         let total_pool_tokens;
         {
             let swap_pool_state_account = &mut ctx.accounts.swap_pool_state_account;
@@ -884,6 +1172,10 @@ pub mod swap_pool {
         
         ctx.accounts.mint_pool_tokens_for_recipient(total_pool_tokens.as_u64())?;
 
+        // NOTE: the loaded mint data is not updated by the mint operation
+        let pool_token_supply = ctx.accounts.swap_pool_token_mint.supply.checked_add(total_pool_tokens.as_u64()).unwrap();
+        ctx.accounts.swap_pool_state_account.checkpoint_pool_token_supply(Clock::get()?.slot, pool_token_supply);
+
         emit!(InLiquiditySwapEvent {
             swap_pool: ctx.accounts.swap_pool_state_account.key(),
         
@@ -898,78 +1190,906 @@ pub mod swap_pool {
         Ok(())
     }
 
-}
 
+    // Queries ******************************************************************************************************************
 
+    pub fn version(_ctx: Context<VersionQuery>) -> Result<VersionResponse> {
+        Ok(VersionResponse {
+            contract: env!("CARGO_PKG_NAME").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string()
+        })
+    }
 
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(mut)]
-    pub setup_master: Signer<'info>,
-    pub dao_authority: Signer<'info>,
-    #[account(init, payer = setup_master, space = SwapPoolState::LEN)]  // NOTE: LEN includes discriminator
-    pub swap_pool_state_account: Box<Account<'info, SwapPoolState>>,
+    pub fn min_initial_liquidity(_ctx: Context<PoolQuery>) -> Result<MinInitialLiquidityResponse> {
+        Ok(MinInitialLiquidityResponse {
+            initial_pool_tokens: INITIAL_POOL_TOKENS,
+            locked_pool_tokens: LOCKED_POOL_TOKENS
+        })
+    }
 
-    // Pool token accounts
-    #[account(
-        init,
-        seeds = [
-            &swap_pool_state_account.key().to_bytes(),
-            POOL_TOKEN_MINT_SEED
-        ],
-        bump,
-        payer = setup_master,
-        mint::decimals = 0, // TODO!
-        mint::authority = swap_pool_authority
-    )]
-    pub swap_pool_token_mint: Account<'info, Mint>,
+    pub fn min_out_reference(_ctx: Context<PoolQuery>) -> Result<MinOutReferenceResponse> {
+        Ok(MinOutReferenceResponse {
+            asset_swap_min_out_basis: "to_asset".to_string(),
+            asset_swap_max_min_out: u64::MAX,
+            liquidity_swap_min_out_basis: "none".to_string()
+        })
+    }
 
-    /// CHECK: Safe, using as an argument
-    pub depositor_pool_token_wallet_authority: UncheckedAccount<'info>,
-    #[account(
-        init,
-        payer = setup_master,
-        token::mint = swap_pool_token_mint,
-        token::authority = depositor_pool_token_wallet_authority
-    )]
-    pub depositor_pool_token_wallet: Account<'info, TokenAccount>, // The token wallet of the pool depositor where to transfer the pool tokens
+    pub fn capabilities(_ctx: Context<PoolQuery>) -> Result<Vec<String>> {
+        Ok(vault_capabilities!())
+    }
 
-    #[account(
-        seeds = [
-            &swap_pool_state_account.key().to_bytes(),
-            POOL_AUTHORITY_SEED
-        ],
-        bump
-    )]
-    /// CHECK: Safe, as we are not reading from the account.
-    pub swap_pool_authority: UncheckedAccount<'info>,
+    pub fn vault_type(_ctx: Context<PoolQuery>) -> Result<VaultTypeResponse> {
+        Ok(VaultTypeResponse {
+            kind: VAULT_TYPE.to_string(),
+            schema_version: VAULT_SCHEMA_VERSION
+        })
+    }
 
+    pub fn config(ctx: Context<PoolQuery>) -> Result<ConfigResponse> {
+        let swap_pool_state_account = &ctx.accounts.swap_pool_state_account;
+
+        Ok(ConfigResponse {
+            kind: VAULT_TYPE.to_string(),
+            pool_assets_mints: swap_pool_state_account.pool_assets_mints,
+            pool_assets_weights: swap_pool_state_account.pool_assets_weights,
+            amplification_x64: [0, 1, 0, 0],       // Volatile pools are not amplified (k = 1)
+            min_swap_amounts: swap_pool_state_account.min_swap_amounts,
+            ibc_interface: swap_pool_state_account.ibc_interface,
+            dao_authority: swap_pool_state_account.dao_authority,
+            setup_master: swap_pool_state_account.setup_master
+        })
+    }
 
-    // Sys and generics
-    pub rent: Sysvar<'info, Rent>,
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>
-}
+    pub fn cumulative_volume(ctx: Context<PoolQuery>) -> Result<CumulativeVolumeResponse> {
+        Ok(CumulativeVolumeResponse {
+            volume_in: ctx.accounts.swap_pool_state_account.volume_in,
+            volume_out: ctx.accounts.swap_pool_state_account.volume_out
+        })
+    }
 
-impl<'info> Initialize<'info> {
+    pub fn asset_limit_capacity(ctx: Context<PoolQuery>, asset: Pubkey) -> Result<u64> {
 
-    pub fn create_swap_pool_asset_wallet(
-        &self,
-        asset_mint: AccountInfo<'info>,
-        swap_pool_asset_wallet: AccountInfo<'info>
-    ) -> Result<u8> {
+        let asset_index = ctx.accounts.swap_pool_state_account.get_asset_index(&asset)
+            .ok_or(error!(ErrorCode::InvalidAssetMintAccount))?;
 
-        // Make sure the swap_pool_asset_wallet provided matches the expected pda
-        let seeds: &[&[u8]] = &[
-            &self.swap_pool_state_account.key().to_bytes(),
-            &asset_mint.key().to_bytes(),
-            POOL_ASSET_WALLET_SEED
-        ];
+        let current_timestamp: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
 
-        let (
-            expected_swap_pool_asset_wallet, 
-            swap_pool_asset_wallet_bump
-        ) = Pubkey::find_program_address(seeds, &ID);
+        Ok(ctx.accounts.swap_pool_state_account.get_asset_limit_capacity(asset_index, current_timestamp))
+    }
+
+    // NOTE: The security limits only apply to the incoming swaps (the units inflow and the asset outflows). Outgoing swaps
+    // free up units inflow capacity rather than consuming any, hence a send never exceeds a limit on the source side and
+    // the remaining capacity is unbounded (u64::MAX).
+    pub fn would_send_exceed_limit(
+        ctx: Context<PoolQuery>,
+        from_asset: Pubkey,
+        _amount: u64
+    ) -> Result<WouldSendExceedLimitResponse> {
+
+        ctx.accounts.swap_pool_state_account.get_asset_index(&from_asset)
+            .ok_or(error!(ErrorCode::InvalidAssetMintAccount))?;
+
+        Ok(WouldSendExceedLimitResponse {
+            exceeds_limit: false,
+            remaining_capacity: u64::MAX
+        })
+    }
+
+    // Both accumulators are brought up to the current time with the current pool balances, so that they refer to the same
+    // timestamp.
+    pub fn price_accumulator<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, PoolQuery<'info>>,
+        from_asset: Pubkey,
+        to_asset: Pubkey
+    ) -> Result<PriceAccumulatorResponse> {
+
+        // 1 Account given per pool asset:
+        //      - Swap pool asset wallet
+        let swap_pool_state_account = &ctx.accounts.swap_pool_state_account;
+
+        let from_asset_index = swap_pool_state_account.get_asset_index(&from_asset)
+            .ok_or(error!(ErrorCode::InvalidAssetMintAccount))?;
+
+        let to_asset_index = swap_pool_state_account.get_asset_index(&to_asset)
+            .ok_or(error!(ErrorCode::InvalidAssetMintAccount))?;
+
+        let balances = swap_pool_state_account.get_available_balances(
+            &swap_pool_state_account.key(),
+            ctx.remaining_accounts
+        )?;
+
+        let current_timestamp: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
+
+        Ok(PriceAccumulatorResponse {
+            from_price_cumulative_x64: swap_pool_state_account.get_price_cumulative_x64(
+                from_asset_index,
+                balances[from_asset_index],
+                current_timestamp
+            )?.0,
+            to_price_cumulative_x64: swap_pool_state_account.get_price_cumulative_x64(
+                to_asset_index,
+                balances[to_asset_index],
+                current_timestamp
+            )?.0,
+            timestamp: current_timestamp
+        })
+    }
+
+    pub fn escrow_counts(ctx: Context<PoolQuery>) -> Result<EscrowCountsResponse> {
+        Ok(EscrowCountsResponse {
+            asset_escrows: ctx.accounts.swap_pool_state_account.open_escrows
+        })
+    }
+
+    pub fn escrow_details(
+        ctx: Context<EscrowDetailsQuery>,
+        _escrow_nonce: u32              // Used in the EscrowDetailsQuery context
+    ) -> Result<EscrowDetailsResponse> {
+        let swap_escrow = &ctx.accounts.swap_escrow;
+
+        Ok(EscrowDetailsResponse {
+            asset_mint: ctx.accounts.swap_pool_state_account.pool_assets_mints[swap_escrow.asset_index as usize],
+            amount: swap_escrow.amount,
+            fallback_wallet: swap_escrow.fallback_wallet,
+            target_chain: swap_escrow.target_chain,
+            target_pool: swap_escrow.target_pool,
+            to_asset_index: swap_escrow.to_asset_index,
+            target_withdrawer: swap_escrow.target_withdrawer,
+            units_x64: swap_escrow.units_x64
+        })
+    }
+
+    // The remote asset list is supplied by the caller (cross-chain queries are not available): it must contain the mints of
+    // this pool in the order in which the remote pool will reference them via `target_asset_index`.
+    pub fn check_connection_compatibility(
+        ctx: Context<PoolQuery>,
+        expected_assets: Vec<Pubkey>
+    ) -> Result<ConnectionCompatibilityResponse> {
+        let swap_pool_state_account = &ctx.accounts.swap_pool_state_account;
+
+        let local_asset_count = swap_pool_state_account.get_asset_count();
+
+        let mismatched_indices: Vec<u8> = expected_assets
+            .iter()
+            .enumerate()
+            .filter(|(i, asset)| *i >= local_asset_count || swap_pool_state_account.pool_assets_mints[*i] != **asset)
+            .map(|(i, _)| i as u8)
+            .collect();
+
+        Ok(ConnectionCompatibilityResponse {
+            compatible: local_asset_count == expected_assets.len() && mismatched_indices.is_empty(),
+            local_asset_count: local_asset_count as u8,
+            expected_asset_count: expected_assets.len() as u8,
+            mismatched_indices
+        })
+    }
+
+    pub fn is_dao_authority(ctx: Context<PoolQuery>, address: Pubkey) -> Result<bool> {
+        Ok(ctx.accounts.swap_pool_state_account.dao_authority == address)
+    }
+
+    pub fn setup_deadline(ctx: Context<PoolQuery>) -> Result<u64> {
+
+        let swap_pool_state_account = &ctx.accounts.swap_pool_state_account;
+
+        // Once the setup is finished there is no time left for the setup_master
+        if swap_pool_state_account.setup_master == Pubkey::default() {
+            return Ok(0);
+        }
+
+        let current_timestamp: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
+
+        Ok(swap_pool_state_account.setup_deadline.saturating_sub(current_timestamp))
+    }
+
+    // Returns the pool token supply recorded by the latest checkpoint at or before the given slot. Checkpoints are only
+    // written by the pool operations that mint or burn pool tokens (taking the mint supply at that time as the base), hence
+    // pool tokens burnt directly through the token program are not reflected until the next such pool operation.
+    pub fn pool_token_supply_at(ctx: Context<PoolQuery>, slot: u64) -> Result<u64> {
+        ctx.accounts.swap_pool_state_account.get_pool_token_supply_at(slot)
+    }
+
+    pub fn deposit_cap(ctx: Context<PoolQuery>, asset: Pubkey) -> Result<u64> {
+
+        let asset_index = ctx.accounts.swap_pool_state_account.get_asset_index(&asset)
+            .ok_or(error!(ErrorCode::InvalidAssetMintAccount))?;
+
+        Ok(ctx.accounts.swap_pool_state_account.deposit_caps[asset_index])
+    }
+
+    pub fn min_swap_amount(ctx: Context<PoolQuery>, asset: Pubkey) -> Result<u64> {
+
+        let asset_index = ctx.accounts.swap_pool_state_account.get_asset_index(&asset)
+            .ok_or(error!(ErrorCode::InvalidAssetMintAccount))?;
+
+        Ok(ctx.accounts.swap_pool_state_account.min_swap_amounts[asset_index])
+    }
+
+    pub fn normalized_balances<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, PoolQuery<'info>>
+    ) -> Result<NormalizedBalancesResponse> {
+
+        // 1 Account given per pool asset:
+        //      - Swap pool asset wallet
+        let swap_pool_state_account = &ctx.accounts.swap_pool_state_account;
+
+        let balances = swap_pool_state_account.get_available_balances(
+            &swap_pool_state_account.key(),
+            ctx.remaining_accounts
+        )?;
+
+        let mut normalized_balances: [[u64; 4]; NUMASSETS] = [[0; 4]; NUMASSETS];
+        for asset_index in 0..NUMASSETS {
+            normalized_balances[asset_index] = U256::from(balances[asset_index])
+                .checked_mul(U256::from(swap_pool_state_account.pool_assets_weights[asset_index])).unwrap()
+                .0;
+        }
+
+        Ok(NormalizedBalancesResponse { normalized_balances })
+    }
+
+    // Values every pool asset at the marginal price of the pool: Bi·(Wi/Bi)/(Wr/Br) = Br·Wi/Wr units of the reference asset.
+    pub fn total_value_locked<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, PoolQuery<'info>>,
+        reference_asset: Pubkey
+    ) -> Result<u64> {
+
+        // 1 Account given per pool asset:
+        //      - Swap pool asset wallet
+        let swap_pool_state_account = &ctx.accounts.swap_pool_state_account;
+
+        let reference_asset_index = swap_pool_state_account.get_asset_index(&reference_asset)
+            .ok_or(error!(ErrorCode::InvalidAssetMintAccount))?;
+
+        // Unused asset slots (default mint, zero weight) cannot be used to value the pool
+        if reference_asset.eq(&Pubkey::default()) || swap_pool_state_account.pool_assets_weights[reference_asset_index] == 0 {
+            return Err(error!(ErrorCode::InvalidAssetMintAccount));
+        }
+
+        let balances = swap_pool_state_account.get_available_balances(
+            &swap_pool_state_account.key(),
+            ctx.remaining_accounts
+        )?;
+
+        let reference_balance = U256::from(balances[reference_asset_index]);
+        let reference_weight  = U256::from(swap_pool_state_account.pool_assets_weights[reference_asset_index]);
+
+        let mut total_value = U256::zero();
+        for asset_index in 0..swap_pool_state_account.get_asset_count() {
+            total_value = total_value.checked_add(
+                reference_balance
+                    .checked_mul(U256::from(swap_pool_state_account.pool_assets_weights[asset_index]))
+                    .ok_or(error!(ErrorCode::ArithmeticOverflow))?
+                    .checked_div(reference_weight)
+                    .ok_or(error!(ErrorCode::InvalidAssetMintAccount))?
+            ).ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+        }
+
+        let total_value: u64 = total_value.try_into().map_err(|_| error!(ErrorCode::ArithmeticOverflow))?;
+
+        Ok(total_value)
+    }
+
+    // Assets that can currently be swapped into from the given asset, i.e. the other pool assets with a nonzero available
+    // balance.
+    pub fn swappable_assets<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, PoolQuery<'info>>,
+        from_asset: Pubkey
+    ) -> Result<Vec<Pubkey>> {
+
+        // 1 Account given per pool asset:
+        //      - Swap pool asset wallet
+        let swap_pool_state_account = &ctx.accounts.swap_pool_state_account;
+
+        let from_asset_index = swap_pool_state_account.get_asset_index(&from_asset)
+            .ok_or(error!(ErrorCode::InvalidAssetMintAccount))?;
+
+        let balances = swap_pool_state_account.get_available_balances(
+            &swap_pool_state_account.key(),
+            ctx.remaining_accounts
+        )?;
+
+        Ok(
+            (0..swap_pool_state_account.get_asset_count())
+                .filter(|asset_index| *asset_index != from_asset_index && balances[*asset_index] > 0)
+                .map(|asset_index| swap_pool_state_account.pool_assets_mints[asset_index])
+                .collect()
+        )
+    }
+
+    pub fn assets_metadata<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, PoolQuery<'info>>
+    ) -> Result<AssetsMetadataResponse> {
+
+        // 1 Account given per pool asset:
+        //      - Asset mint
+        let swap_pool_state_account = &ctx.accounts.swap_pool_state_account;
+
+        let asset_count = swap_pool_state_account.get_asset_count();
+
+        if ctx.remaining_accounts.len() != asset_count {
+            return Err(error!(ErrorCode::InvalidRemainingAccountsCount));
+        }
+
+        let mut decimals: [u8; NUMASSETS] = [0; NUMASSETS];
+        for_each_asset(asset_count, NUMASSETS, || error!(ErrorCode::AssetCountExceeded), |asset_index| {
+
+            let asset_mint = &ctx.remaining_accounts[asset_index];
+
+            if *asset_mint.key != swap_pool_state_account.pool_assets_mints[asset_index] {
+                return Err(error!(ErrorCode::InvalidAssetMintAccount));
+            }
+
+            decimals[asset_index] = Account::<Mint>::try_from(asset_mint)?.decimals;
+
+            Ok(())
+        })?;
+
+        Ok(AssetsMetadataResponse {
+            pool_assets_mints: swap_pool_state_account.pool_assets_mints,
+            decimals
+        })
+    }
+
+    pub fn max_receivable_units<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, PoolQuery<'info>>,
+        to_asset: Pubkey
+    ) -> Result<MaxReceivableUnitsResponse> {
+
+        // 1 Account given per pool asset:
+        //      - Swap pool asset wallet
+        let swap_pool_state_account = &ctx.accounts.swap_pool_state_account;
+
+        let to_asset_index = swap_pool_state_account.get_asset_index(&to_asset)
+            .ok_or(error!(ErrorCode::InvalidAssetMintAccount))?;
+
+        let balances = swap_pool_state_account.get_available_balances(
+            &swap_pool_state_account.key(),
+            ctx.remaining_accounts
+        )?;
+
+        let current_timestamp: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
+
+        let max_units_x64 = swap_pool_state_account.get_max_receivable_units_x64(
+            to_asset_index,
+            balances[to_asset_index],
+            current_timestamp
+        )?;
+
+        Ok(MaxReceivableUnitsResponse { units_x64: max_units_x64.0 })
+    }
+
+    // Errors with UnitsExceedPool if the units cannot be honored by the pool (see calculation_helpers::in_swap)
+    pub fn calc_receive_asset<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, PoolQuery<'info>>,
+        to_asset: Pubkey,
+        units_x64: [u64; 4],
+        approx: bool
+    ) -> Result<u64> {
+
+        // 1 Account given per pool asset:
+        //      - Swap pool asset wallet
+        let swap_pool_state_account = &ctx.accounts.swap_pool_state_account;
+
+        let to_asset_index = swap_pool_state_account.get_asset_index(&to_asset)
+            .ok_or(error!(ErrorCode::InvalidAssetMintAccount))?;
+
+        let balances = swap_pool_state_account.get_available_balances(
+            &swap_pool_state_account.key(),
+            ctx.remaining_accounts
+        )?;
+
+        Ok(calculation_helpers::in_swap(
+            U256(units_x64),
+            U256::from(balances[to_asset_index]),
+            U256::from(swap_pool_state_account.pool_assets_weights[to_asset_index]),
+            approx
+        )?.as_u64())      // The output is bounded by the pool balance
+    }
+
+    // Output asset received per unit (x64) at the current pool state, i.e. the derivative of calc_receive_asset. For small
+    // units, calc_receive_asset(units) ≈ units · marginal_receive_rate.
+    pub fn marginal_receive_rate<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, PoolQuery<'info>>,
+        to_asset: Pubkey
+    ) -> Result<[u64; 4]> {
+
+        // 1 Account given per pool asset:
+        //      - Swap pool asset wallet
+        let swap_pool_state_account = &ctx.accounts.swap_pool_state_account;
+
+        let to_asset_index = swap_pool_state_account.get_asset_index(&to_asset)
+            .ok_or(error!(ErrorCode::InvalidAssetMintAccount))?;
+
+        let balances = swap_pool_state_account.get_available_balances(
+            &swap_pool_state_account.key(),
+            ctx.remaining_accounts
+        )?;
+
+        Ok(calculation_helpers::marginal_in_swap_x64(
+            U256::from(balances[to_asset_index]),
+            U256::from(swap_pool_state_account.pool_assets_weights[to_asset_index])
+        )?.0)
+    }
+
+    // Output of an incoming swap that is guaranteed not to be rejected by the security limits. If the given units exceed the
+    // ones receivable within the limits, the output of the largest receivable swap is returned instead (i.e. less than the
+    // calc_receive_asset output, as the full swap would be rejected)
+    pub fn guaranteed_receive_asset<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, PoolQuery<'info>>,
+        to_asset: Pubkey,
+        units_x64: [u64; 4],
+        approx: bool
+    ) -> Result<u64> {
+
+        // 1 Account given per pool asset:
+        //      - Swap pool asset wallet
+        let swap_pool_state_account = &ctx.accounts.swap_pool_state_account;
+
+        let to_asset_index = swap_pool_state_account.get_asset_index(&to_asset)
+            .ok_or(error!(ErrorCode::InvalidAssetMintAccount))?;
+
+        let balances = swap_pool_state_account.get_available_balances(
+            &swap_pool_state_account.key(),
+            ctx.remaining_accounts
+        )?;
+
+        let current_timestamp: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
+
+        let max_units_x64 = swap_pool_state_account.get_max_receivable_units_x64(
+            to_asset_index,
+            balances[to_asset_index],
+            current_timestamp
+        )?;
+
+        Ok(calculation_helpers::in_swap(
+            U256(units_x64).min(max_units_x64),
+            U256::from(balances[to_asset_index]),
+            U256::from(swap_pool_state_account.pool_assets_weights[to_asset_index]),
+            approx
+        )?.as_u64())      // The output is bounded by the pool balance
+    }
+
+    // State of the pool after an outgoing swap of the given amount (see out_swap), without performing it
+    pub fn simulate_send_asset<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, PoolQuery<'info>>,
+        from_asset: Pubkey,
+        amount: u64,
+        approx: bool
+    ) -> Result<SimulateSendAssetResponse> {
+
+        // 1 Account given per pool asset:
+        //      - Swap pool asset wallet
+        let swap_pool_state_account = &ctx.accounts.swap_pool_state_account;
+
+        let from_asset_index = swap_pool_state_account.get_asset_index(&from_asset)
+            .ok_or(error!(ErrorCode::InvalidAssetMintAccount))?;
+
+        let balances = swap_pool_state_account.get_available_balances(
+            &swap_pool_state_account.key(),
+            ctx.remaining_accounts
+        )?;
+
+        // NOTE: out_swap computes the units using the whole wallet balance (i.e. including the escrowed assets)
+        let escrowed_amount = swap_pool_state_account.escrowed_assets[from_asset_index];
+        let wallet_balance  = balances[from_asset_index].checked_add(escrowed_amount).unwrap();
+
+        let units_x64 = calculation_helpers::out_swap_x64(
+            U256::from(amount),
+            U256::from(wallet_balance),
+            U256::from(swap_pool_state_account.pool_assets_weights[from_asset_index]),
+            approx
+        )?;
+
+        Ok(SimulateSendAssetResponse {
+            units_x64: units_x64.0,
+            asset_wallet_balance: wallet_balance.checked_add(amount).unwrap(),
+            escrowed_asset_amount: escrowed_amount.checked_add(amount).unwrap(),
+            current_units_inflow_x64: U256(swap_pool_state_account.current_units_inflow_x64).saturating_sub(units_x64).0
+        })
+    }
+
+    // What-if analysis of a local swap under a hypothetical vault fee (the pool itself does not charge fees)
+    pub fn calc_local_swap_with_fee<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, PoolQuery<'info>>,
+        from_asset: Pubkey,
+        to_asset: Pubkey,
+        amount: u64,
+        vault_fee_bps: u64,
+        approx: bool
+    ) -> Result<u64> {
+
+        // 1 Account given per pool asset:
+        //      - Swap pool asset wallet
+        let swap_pool_state_account = &ctx.accounts.swap_pool_state_account;
+
+        let from_asset_index = swap_pool_state_account.get_asset_index(&from_asset)
+            .ok_or(error!(ErrorCode::InvalidAssetMintAccount))?;
+
+        let to_asset_index = swap_pool_state_account.get_asset_index(&to_asset)
+            .ok_or(error!(ErrorCode::InvalidAssetMintAccount))?;
+
+        let balances = swap_pool_state_account.get_available_balances(
+            &swap_pool_state_account.key(),
+            ctx.remaining_accounts
+        )?;
+
+        // The fee is taken from the input amount (rounded up, in favour of the vault)
+        let fee = compute_fee(amount, vault_fee_bps).map_err(|_| error!(ErrorCode::InvalidVaultFee))?;
+        let amount_after_fee = amount - fee;
+
+        // Like local_swap, the input asset escrowed amount is not excluded from the input asset balance
+        let out = calculation_helpers::full_swap(
+            U256::from(amount_after_fee),
+            U256::from(balances[from_asset_index].checked_add(swap_pool_state_account.escrowed_assets[from_asset_index]).unwrap()),
+            U256::from(swap_pool_state_account.pool_assets_weights[from_asset_index]),
+            U256::from(balances[to_asset_index]),
+            U256::from(swap_pool_state_account.pool_assets_weights[to_asset_index]),
+            approx
+        )?.as_u64();      // U256 to u64 will panic if overflow
+
+        Ok(out)
+    }
+
+    pub fn price_impact<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, PoolQuery<'info>>,
+        from_asset: Pubkey,
+        to_asset: Pubkey,
+        amount: u64
+    ) -> Result<u64> {
+
+        // 1 Account given per pool asset:
+        //      - Swap pool asset wallet
+        let swap_pool_state_account = &ctx.accounts.swap_pool_state_account;
+
+        let from_asset_index = swap_pool_state_account.get_asset_index(&from_asset)
+            .ok_or(error!(ErrorCode::InvalidAssetMintAccount))?;
+
+        let to_asset_index = swap_pool_state_account.get_asset_index(&to_asset)
+            .ok_or(error!(ErrorCode::InvalidAssetMintAccount))?;
+
+        let balances = swap_pool_state_account.get_available_balances(
+            &swap_pool_state_account.key(),
+            ctx.remaining_accounts
+        )?;
+
+        // Like local_swap, the input asset escrowed amount is not excluded from the input asset balance
+        let from_asset_balance = U256::from(balances[from_asset_index].checked_add(swap_pool_state_account.escrowed_assets[from_asset_index]).unwrap());
+        let from_asset_weight  = U256::from(swap_pool_state_account.pool_assets_weights[from_asset_index]);
+        let to_asset_balance   = U256::from(balances[to_asset_index]);
+        let to_asset_weight    = U256::from(swap_pool_state_account.pool_assets_weights[to_asset_index]);
+
+        let out = calculation_helpers::full_swap(
+            U256::from(amount),
+            from_asset_balance,
+            from_asset_weight,
+            to_asset_balance,
+            to_asset_weight,
+            false       // Exact output
+        )?;
+
+        calculation_helpers::calc_price_impact_bps(
+            U256::from(amount),
+            out,
+            from_asset_balance,
+            from_asset_weight,
+            to_asset_balance,
+            to_asset_weight
+        )
+    }
+
+    // Returns the largest input amount whose price impact does not exceed max_impact_bps (capped at
+    // DEPTH_SEARCH_MAX_BALANCE_MULTIPLE·At). The depth is bracketed by doubling from the input asset balance At, and then
+    // bisected down to a single unit.
+    pub fn depth_to_impact<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, PoolQuery<'info>>,
+        from_asset: Pubkey,
+        to_asset: Pubkey,
+        max_impact_bps: u64
+    ) -> Result<u64> {
+
+        // 1 Account given per pool asset:
+        //      - Swap pool asset wallet
+        let swap_pool_state_account = &ctx.accounts.swap_pool_state_account;
+
+        let from_asset_index = swap_pool_state_account.get_asset_index(&from_asset)
+            .ok_or(error!(ErrorCode::InvalidAssetMintAccount))?;
+
+        let to_asset_index = swap_pool_state_account.get_asset_index(&to_asset)
+            .ok_or(error!(ErrorCode::InvalidAssetMintAccount))?;
+
+        let balances = swap_pool_state_account.get_available_balances(
+            &swap_pool_state_account.key(),
+            ctx.remaining_accounts
+        )?;
+
+        // Like local_swap, the input asset escrowed amount is not excluded from the input asset balance
+        let from_balance = balances[from_asset_index].checked_add(swap_pool_state_account.escrowed_assets[from_asset_index]).unwrap();
+
+        let from_asset_balance = U256::from(from_balance);
+        let from_asset_weight  = U256::from(swap_pool_state_account.pool_assets_weights[from_asset_index]);
+        let to_asset_balance   = U256::from(balances[to_asset_index]);
+        let to_asset_weight    = U256::from(swap_pool_state_account.pool_assets_weights[to_asset_index]);
+
+        let price_impact_bps = |amount: u64| -> Result<u64> {
+            let out = calculation_helpers::full_swap(
+                U256::from(amount),
+                from_asset_balance,
+                from_asset_weight,
+                to_asset_balance,
+                to_asset_weight,
+                false       // Exact output
+            )?;
+
+            calculation_helpers::calc_price_impact_bps(
+                U256::from(amount),
+                out,
+                from_asset_balance,
+                from_asset_weight,
+                to_asset_balance,
+                to_asset_weight
+            )
+        };
+
+        let max_depth: u64 = from_balance.max(1).saturating_mul(DEPTH_SEARCH_MAX_BALANCE_MULTIPLE);
+
+        // Invariant: price_impact_bps(low) <= max_impact_bps < price_impact_bps(high)
+        let mut low: u64 = 0;
+        let mut high: u64 = from_balance.max(1);
+
+        while price_impact_bps(high)? <= max_impact_bps {
+            if high >= max_depth { return Ok(max_depth) }
+
+            low  = high;
+            high = high.saturating_mul(2).min(max_depth);
+        }
+
+        while low + 1 < high {
+            let mid = low + (high - low) / 2;
+            if price_impact_bps(mid)? <= max_impact_bps {
+                low = mid;
+            }
+            else {
+                high = mid;
+            }
+        }
+
+        Ok(low)
+    }
+
+}
+
+
+
+// Protects swaps against being executed long after they were signed (e.g. if the transaction is delayed)
+fn verify_deadline(deadline: Option<u64>) -> Result<()> {
+
+    if let Some(deadline) = deadline {
+        let current_timestamp: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
+        if current_timestamp > deadline {
+            return Err(error!(ErrorCode::DeadlineExpired));
+        }
+    }
+
+    Ok(())
+}
+
+fn withdraw_assets<'a, 'b, 'c, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, Withdraw<'info>>,
+    pool_tokens_amount: u64,
+    to: Option<Pubkey>,             // If specified, the withdrawn assets must be sent to token wallets owned by this account
+    min_amounts: [u64; NUMASSETS]
+) -> Result<()> {
+
+    // Update the liqudity security limit. Since the limit is based on the current totalSupply, changing the totalSupply
+    // downwards by withdrawing changes the limit.
+    let current_timestamp: u64 = Clock::get().unwrap().unix_timestamp.try_into().unwrap();
+    ctx.accounts.swap_pool_state_account.update_liquidity_units_inflow(
+        0,
+        ctx.accounts.swap_pool_token_mint.supply,
+        current_timestamp
+    )?;
+
+    // Burn pool tokens from the withdrawer wallet.
+    ctx.accounts.burn_pool_tokens_of_withdrawer(pool_tokens_amount)?;
+
+    let mut withdrawn_amounts: [u64; NUMASSETS] = [0; NUMASSETS];
+
+    let swap_pool_state_account = &mut ctx.accounts.swap_pool_state_account;
+
+    let pool_assets_mints: [Pubkey; NUMASSETS] = swap_pool_state_account.pool_assets_mints.clone();
+
+    let mut withdrawer_asset_wallets: [Pubkey; NUMASSETS] = [Pubkey::default(); NUMASSETS];
+
+    let initial_pool_tokens_supply = ctx.accounts.swap_pool_token_mint.supply;    // Theoretically, this is not needed, as the 'burn' operation does not update the data that has been loaded from the account, but this is here for clarity
+    
+    // 3 Accounts given per withdrawn asset:
+    //      - Asset mint
+    //      - Swap pool asset wallet
+    //      - Depositor asset wallet
+    for asset_index in 0..NUMASSETS {
+
+        if swap_pool_state_account.pool_assets_mints[asset_index].eq(&Pubkey::default()) {
+            break;
+        }
+
+        // Verify the withdrawer_asset_wallet and the swap_pool_asset_wallet accounts
+        // ! TODO VERY IMPORTANT, THIS VERIFICATION HAS TO BE REVISED
+        let asset_mint              = &ctx.remaining_accounts[asset_index*3];    // Mint account verified by token account creation cpi // ! TODO verify
+        let swap_pool_asset_wallet  = &ctx.remaining_accounts[asset_index*3+1];  // Verified in 'create_swap_pool_asset_wallet' step
+        let withdrawer_asset_wallet = &ctx.remaining_accounts[asset_index*3+2];  // No need to verify, assets taken from here
+        
+        // Verify the asset mint
+        if asset_mint.key().ne(&pool_assets_mints[asset_index]) {
+            return Err(error!(ErrorCode::InvalidAssetMintAccount));
+        }
+
+        // Make sure the swap_pool_asset_wallet provided matches the expected pda
+        let seeds: &[&[u8]] = &[
+            &swap_pool_state_account.key().to_bytes(),
+            &pool_assets_mints[asset_index].key().to_bytes(),
+            POOL_ASSET_WALLET_SEED,
+            &[swap_pool_state_account.wallets_bumps[asset_index]]
+        ];
+
+        let expected_swap_pool_asset_wallet  = Pubkey::create_program_address(seeds, &ID).unwrap();
+
+        if swap_pool_asset_wallet.key() != expected_swap_pool_asset_wallet {
+            return Err(error!(ErrorCode::InvalidSwapPoolAssetWalletAccount));
+        }
+
+        // Make sure the withdrawer_asset_wallet belongs to the requested recipient
+        if let Some(recipient) = to {
+            if TokenAccount::try_deserialize(&mut &withdrawer_asset_wallet.try_borrow_data()?[..])?.owner != recipient {
+                return Err(error!(ErrorCode::InvalidWithdrawalRecipient));
+            }
+        }
+    
+        let swap_pool_asset_wallet_balance = TokenAccount::try_deserialize(&mut &swap_pool_asset_wallet.try_borrow_data()?[..])?.amount;
+
+        // Compute the asset withdraw amount
+        let asset_eq_balance = swap_pool_state_account.pool_assets_eq_balances[asset_index];
+        let pool_tokens_for_asset = pool_tokens_amount
+            .checked_mul(asset_eq_balance).unwrap()
+            .checked_div(initial_pool_tokens_supply).unwrap();
+        
+        let asset_withdraw_amount = calculation_helpers::calc_asset_amount_for_pool_tokens(
+            pool_tokens_for_asset,
+            swap_pool_asset_wallet_balance
+                .checked_sub(swap_pool_state_account.escrowed_assets[asset_index]).unwrap(),         // Escrowed tokens ARE subtracted from the total balance => withdrawals should return less
+            asset_eq_balance
+        )?;
+
+        if asset_withdraw_amount < min_amounts[asset_index] {
+            return Err(error!(ErrorCode::WithdrawMinAmountNotFulfilled));
+        }
+
+        // Update asset eq balance
+        swap_pool_state_account.pool_assets_eq_balances[asset_index] = asset_eq_balance.checked_sub(pool_tokens_for_asset).unwrap();
+
+        // Accumulate the price before the withdrawal
+        let available_balance = swap_pool_asset_wallet_balance.checked_sub(swap_pool_state_account.escrowed_assets[asset_index]).unwrap();
+        swap_pool_state_account.update_price_accumulator(asset_index, available_balance, current_timestamp)?;
+    
+        token_utils::transfer_tokens_using_pda_authority(
+            asset_withdraw_amount,
+            swap_pool_asset_wallet.to_account_info(),
+            withdrawer_asset_wallet.to_account_info(),
+            ctx.accounts.swap_pool_authority.to_account_info(),
+            &[
+                &swap_pool_state_account.key().to_bytes(), // SwapPool state account
+                &POOL_AUTHORITY_SEED[..],                  // Pool authority seed
+                &[swap_pool_state_account.authority_bump]  // PDA bump
+            ],
+            ctx.accounts.token_program.to_account_info()
+        );
+
+        // Save data for logging
+        withdrawer_asset_wallets[asset_index] = withdrawer_asset_wallet.key();
+        withdrawn_amounts[asset_index] = asset_withdraw_amount;
+    }
+
+    let pool_token_supply = initial_pool_tokens_supply.checked_sub(pool_tokens_amount).unwrap();
+    ctx.accounts.swap_pool_state_account.checkpoint_pool_token_supply(Clock::get()?.slot, pool_token_supply);
+
+    emit!(WithdrawEvent {
+        swap_pool: ctx.accounts.swap_pool_state_account.key(),
+    
+        withdrawn_asset_amounts: withdrawn_amounts,
+        withdrawer_asset_wallets: withdrawer_asset_wallets,
+    
+        burnt_pool_token_amount: pool_tokens_amount,
+        withdrawer_pool_token_wallet: ctx.accounts.withdrawer_pool_token_wallet.key(),
+    });
+
+    Ok(())
+}
+
+
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(mut)]
+    pub setup_master: Signer<'info>,
+    pub dao_authority: Signer<'info>,
+    #[account(init, payer = setup_master, space = SwapPoolState::LEN)]  // NOTE: LEN includes discriminator
+    pub swap_pool_state_account: Box<Account<'info, SwapPoolState>>,
+
+    // Pool token accounts
+    #[account(
+        init,
+        seeds = [
+            &swap_pool_state_account.key().to_bytes(),
+            POOL_TOKEN_MINT_SEED
+        ],
+        bump,
+        payer = setup_master,
+        mint::decimals = 0, // TODO!
+        mint::authority = swap_pool_authority
+    )]
+    pub swap_pool_token_mint: Account<'info, Mint>,
+
+    /// CHECK: Safe, using as an argument
+    pub depositor_pool_token_wallet_authority: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = setup_master,
+        token::mint = swap_pool_token_mint,
+        token::authority = depositor_pool_token_wallet_authority
+    )]
+    pub depositor_pool_token_wallet: Account<'info, TokenAccount>, // The token wallet of the pool depositor where to transfer the pool tokens
+
+    #[account(
+        seeds = [
+            &swap_pool_state_account.key().to_bytes(),
+            POOL_AUTHORITY_SEED
+        ],
+        bump
+    )]
+    /// CHECK: Safe, as we are not reading from the account.
+    pub swap_pool_authority: UncheckedAccount<'info>,
+
+    // Holds the pool tokens locked on initialization (rescue_token rejects it, no instruction transfers from it)
+    #[account(
+        init,
+        seeds = [
+            &swap_pool_state_account.key().to_bytes(),
+            POOL_LOCKED_TOKENS_SEED
+        ],
+        bump,
+        payer = setup_master,
+        token::mint = swap_pool_token_mint,
+        token::authority = swap_pool_authority
+    )]
+    pub swap_pool_locked_token_wallet: Account<'info, TokenAccount>,
+
+
+    // Sys and generics
+    pub rent: Sysvar<'info, Rent>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>
+}
+
+impl<'info> Initialize<'info> {
+
+    pub fn create_swap_pool_asset_wallet(
+        &self,
+        asset_mint: AccountInfo<'info>,
+        swap_pool_asset_wallet: AccountInfo<'info>
+    ) -> Result<u8> {
+
+        // Make sure the swap_pool_asset_wallet provided matches the expected pda
+        let seeds: &[&[u8]] = &[
+            &self.swap_pool_state_account.key().to_bytes(),
+            &asset_mint.key().to_bytes(),
+            POOL_ASSET_WALLET_SEED
+        ];
+
+        let (
+            expected_swap_pool_asset_wallet, 
+            swap_pool_asset_wallet_bump
+        ) = Pubkey::find_program_address(seeds, &ID);
 
         if swap_pool_asset_wallet.key() != expected_swap_pool_asset_wallet {
             return Err(error!(ErrorCode::InvalidSwapPoolAssetWalletAccount));
@@ -1039,6 +2159,26 @@ impl<'info> Initialize<'info> {
 
         Ok(())
     }
+
+    pub fn lock_pool_tokens(
+        &self,
+        amount: u64,
+    ) -> Result<()> {
+        token_utils::mint_tokens_using_pda_authority(
+            amount,
+            self.swap_pool_locked_token_wallet.to_account_info(),
+            self.swap_pool_token_mint.to_account_info(),
+            self.swap_pool_authority.to_account_info(),
+            &[
+                    &self.swap_pool_state_account.key().to_bytes(),             // SwapPool state account
+                    &POOL_AUTHORITY_SEED[..],                                   // Pool authority seed
+                    &[self.swap_pool_state_account.authority_bump.to_owned()]   // PDA bump
+                ],
+            self.token_program.to_account_info()
+        );
+
+        Ok(())
+    }
 }
 
 
@@ -1048,7 +2188,7 @@ pub struct CreateConnection<'info> {
     pub authority: Signer<'info>,
     #[account(
         constraint =
-            swap_pool_state_account.setup_master == authority.key() || 
+            (swap_pool_state_account.setup_master == authority.key() && !swap_pool_state_account.setup_expired()?) || 
             swap_pool_state_account.dao_authority == authority.key()
             @ ErrorCode::InvalidAuthority
     )]
@@ -1070,10 +2210,38 @@ pub struct CreateConnection<'info> {
 }
 
 
+#[derive(Accounts)]
+pub struct CreateConnections<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        constraint =
+            (swap_pool_state_account.setup_master == authority.key() && !swap_pool_state_account.setup_expired()?) || 
+            swap_pool_state_account.dao_authority == authority.key()
+            @ ErrorCode::InvalidAuthority
+    )]
+    pub swap_pool_state_account: Account<'info, SwapPoolState>,
+
+    // Accounts for IBCInterface.createConnection() (the connection state accounts are given as remaining accounts)
+    pub ibc_interface_program: Program<'info, IbcInterface>,
+    /// CHECK: Safe, as we are passing it directly to the IBCInterface
+    pub swap_pool_authority: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub rent_payer: Signer<'info>,
+    /// CHECK: Safe, as we are passing it directly to the IBCInterface
+    pub interface_state_account: UncheckedAccount<'info>,
+    /// CHECK: Safe, as we are passing it directly to the IBCInterface
+    pub system_program: UncheckedAccount<'info>,
+}
+
+
 #[derive(Accounts)]
 pub struct LinkIBCInterface<'info> {
     // ! Make sure the provided setup_master matches the one saved in swap_pool_state_account
-    #[account(mut, has_one = setup_master @ ErrorCode::InvalidSetupAuthority)]
+    #[account(
+        mut,
+        has_one = setup_master @ ErrorCode::InvalidSetupAuthority,
+        constraint = !swap_pool_state_account.setup_expired()? @ ErrorCode::SetupExpired
+    )]
     pub swap_pool_state_account: Account<'info, SwapPoolState>,
     #[account(mut)]
     pub setup_master: Signer<'info>,
@@ -1083,7 +2251,11 @@ pub struct LinkIBCInterface<'info> {
 #[derive(Accounts)]
 pub struct FinishSetup<'info> {
     // ! Make sure the provided setup_master matches the one saved in swap_pool_state_account
-    #[account(mut, has_one = setup_master @ ErrorCode::InvalidSetupAuthority)]
+    #[account(
+        mut,
+        has_one = setup_master @ ErrorCode::InvalidSetupAuthority,
+        constraint = !swap_pool_state_account.setup_expired()? @ ErrorCode::SetupExpired
+    )]
     pub swap_pool_state_account: Account<'info, SwapPoolState>,
     #[account(mut)]
     pub setup_master: Signer<'info>,
@@ -1180,6 +2352,94 @@ impl<'info> Withdraw<'info> {
 
 
 
+#[derive(Accounts)]
+pub struct Donate<'info> {
+    #[account(mut)]
+    pub swap_pool_state_account: Account<'info, SwapPoolState>,
+
+    pub donor: Signer<'info>,               // Owner of the donor asset wallets
+
+    pub token_program: Program<'info, Token>
+}
+
+
+
+#[derive(Accounts)]
+pub struct SetMinSwapAmount<'info> {
+    pub dao_authority: Signer<'info>,
+    #[account(mut, has_one = dao_authority @ ErrorCode::InvalidAuthority)]
+    pub swap_pool_state_account: Account<'info, SwapPoolState>,
+}
+
+
+
+#[derive(Accounts)]
+pub struct SetMaxAssetOutflow<'info> {
+    pub dao_authority: Signer<'info>,
+    #[account(mut, has_one = dao_authority @ ErrorCode::InvalidAuthority)]
+    pub swap_pool_state_account: Account<'info, SwapPoolState>,
+}
+
+
+
+#[derive(Accounts)]
+pub struct SetDepositCap<'info> {
+    pub dao_authority: Signer<'info>,
+    #[account(mut, has_one = dao_authority @ ErrorCode::InvalidAuthority)]
+    pub swap_pool_state_account: Account<'info, SwapPoolState>,
+}
+
+
+
+#[derive(Accounts)]
+pub struct SetDaoAuthority<'info> {
+    pub dao_authority: Signer<'info>,
+    #[account(mut, has_one = dao_authority @ ErrorCode::InvalidAuthority)]
+    pub swap_pool_state_account: Account<'info, SwapPoolState>,
+    pub new_dao_authority: Signer<'info>,
+}
+
+
+
+#[derive(Accounts)]
+pub struct RescueToken<'info> {
+    pub dao_authority: Signer<'info>,
+    #[account(has_one = dao_authority @ ErrorCode::InvalidAuthority)]
+    pub swap_pool_state_account: Account<'info, SwapPoolState>,
+
+    #[account(
+        mut,
+        token::authority = swap_pool_authority,
+        constraint =
+            swap_pool_state_account.get_asset_index(&rescued_token_wallet.mint).is_none()
+            @ ErrorCode::CannotRescuePoolAsset,
+        constraint =
+            rescued_token_wallet.key() != Pubkey::find_program_address(
+                &[&swap_pool_state_account.key().to_bytes(), POOL_LOCKED_TOKENS_SEED],
+                &ID
+            ).0
+            @ ErrorCode::CannotRescueLockedPoolTokens
+    )]
+    pub rescued_token_wallet: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub recipient_wallet: Account<'info, TokenAccount>,
+
+    // Wallet authority
+    #[account(
+        seeds = [
+            &swap_pool_state_account.key().to_bytes(),
+            POOL_AUTHORITY_SEED
+        ],
+        bump = swap_pool_state_account.authority_bump
+    )]
+    /// CHECK: Safe, as we are not reading from the account.
+    pub swap_pool_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+
+
 #[derive(Accounts)]
 pub struct LocalSwap<'info> {
     #[account(mut)]
@@ -1271,6 +2531,12 @@ pub struct OutSwap<'info> {
     pub token_program: Program<'info, Token>,
 
     // Escrow account
+    // The fallback wallet receives the escrowed assets if the swap times out. It must hold the input asset, as otherwise
+    // the escrow could never be released.
+    #[account(
+        constraint = fallback_wallet.mint == input_asset_mint.key() @ ErrorCode::InvalidFallbackWalletAccount
+    )]
+    pub fallback_wallet: Account<'info, TokenAccount>,
     #[account(
         init,
         payer = swap_escrow_rent_payer,
@@ -1347,6 +2613,17 @@ pub struct OutSwapAck<'info> {
     /// CHECK: Must match the one saved in swap_escrow
     pub swap_escrow_rent_payer: UncheckedAccount<'info>,
 
+    // Wallet of the escrowed asset (read only, for the price accumulator)
+    #[account(
+        seeds = [
+            &swap_pool_state_account.key().to_bytes(),
+            &swap_pool_state_account.pool_assets_mints[swap_escrow.asset_index as usize].to_bytes(),
+            POOL_ASSET_WALLET_SEED
+        ],
+        bump = swap_pool_state_account.wallets_bumps[swap_escrow.asset_index as usize]
+    )]
+    pub swap_pool_asset_wallet: Account<'info, TokenAccount>,
+
     // Chain Interface
     pub ibc_interface: Signer<'info>,
 
@@ -1599,6 +2876,35 @@ impl<'info> InLiquiditySwap<'info> {
     }
 }
 
+
+
+#[derive(Accounts)]
+pub struct PoolQuery<'info> {
+    pub swap_pool_state_account: Account<'info, SwapPoolState>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_nonce: u32)]
+pub struct EscrowDetailsQuery<'info> {
+    pub swap_pool_state_account: Account<'info, SwapPoolState>,
+    #[account(
+        seeds = [
+            &swap_pool_state_account.key().to_bytes().as_ref(),
+            &escrow_nonce.to_be_bytes().as_ref(),
+            &POOL_ESCROW_SEED
+        ],
+        bump = swap_escrow.bump
+    )]
+    pub swap_escrow: Account<'info, SwapEscrow>
+}
+
+#[derive(Accounts)]
+pub struct VersionQuery<'info> {
+    pub swap_pool_state_account: Account<'info, SwapPoolState>,
+}
+
+
+
 #[account]
 pub struct SwapPoolState {
     pub setup_master: Pubkey,
@@ -1609,6 +2915,16 @@ pub struct SwapPoolState {
     pub pool_assets_eq_balances: [u64; NUMASSETS],
 
     pub escrowed_assets: [u64; NUMASSETS],
+    pub open_escrows: u64,                      // Number of unresolved (neither acked nor timed out) outgoing swaps
+    pub min_swap_amounts: [u64; NUMASSETS],     // Minimum input amount of local and outgoing swaps (0 for no minimum)
+    pub deposit_caps: [u64; NUMASSETS],         // Maximum pool balance of each asset reachable through deposits (0 for no cap)
+    pub setup_deadline: u64,                    // Timestamp after which the setup_master can no longer perform setup operations
+
+    pub volume_in: [[u64; 4]; NUMASSETS],       // Cumulative swapped-in amount of each asset (U256)
+    pub volume_out: [[u64; 4]; NUMASSETS],      // Cumulative swapped-out amount of each asset (U256)
+
+    pub price_cumulatives_x64: [[u64; 4]; NUMASSETS],       // Sum of the asset price (units per token) times the time it was held
+    pub price_cumulatives_timestamps: [u64; NUMASSETS],
 
     pub max_units_inflow_x64: [u64; 4],
     pub current_units_inflow_x64: [u64; 4],
@@ -1617,6 +2933,14 @@ pub struct SwapPoolState {
     pub current_liquidity_inflow: u64,
     pub current_liquidity_inflow_timestamp: u64,
 
+    pub max_asset_outflows: [u64; NUMASSETS],                   // Per-asset limit of the incoming swaps output (0 for no limit)
+    pub current_asset_outflows: [u64; NUMASSETS],
+    pub current_asset_outflows_timestamps: [u64; NUMASSETS],
+
+    pub supply_checkpoints_slots: [u64; SUPPLY_CHECKPOINTS],    // Ring buffer of the slots at which the pool token supply changed
+    pub supply_checkpoints: [u64; SUPPLY_CHECKPOINTS],          // Pool token supply at the end of each checkpoint slot
+    pub supply_checkpoints_count: u64,                          // Number of checkpoints ever written
+
     pub authority_bump: u8,
     pub token_mint_bump: u8,
     pub wallets_bumps: [u8; NUMASSETS]
@@ -1632,11 +2956,25 @@ impl SwapPoolState {
         + 8*NUMASSETS   // pool_assets_weights
         + 8*NUMASSETS   // pool_assets_eq_balances
         + 8*NUMASSETS   // escrowed_assets
+        + 8             // open_escrows
+        + 8*NUMASSETS   // min_swap_amounts
+        + 8*NUMASSETS   // deposit_caps
+        + 8             // setup_deadline
+        + 32*NUMASSETS  // volume_in
+        + 32*NUMASSETS  // volume_out
+        + 32*NUMASSETS  // price_cumulatives_x64
+        + 8*NUMASSETS   // price_cumulatives_timestamps
         + 8*4           // max_units_inflow_x64
         + 8*4           // current_units_inflow_x64
         + 8             // current_units_inflow_timestamp
         + 8             // current_liquidity_inflow
         + 8             // current_liquidity_inflow_timestamp
+        + 8*NUMASSETS   // max_asset_outflows
+        + 8*NUMASSETS   // current_asset_outflows
+        + 8*NUMASSETS   // current_asset_outflows_timestamps
+        + 8*SUPPLY_CHECKPOINTS  // supply_checkpoints_slots
+        + 8*SUPPLY_CHECKPOINTS  // supply_checkpoints
+        + 8             // supply_checkpoints_count
         + 1             // authority_bump
         + 1             // token_mint_bump
         + NUMASSETS;    // wallets_bumps
@@ -1647,6 +2985,200 @@ impl SwapPoolState {
     }
 
 
+    pub fn setup_expired(&self) -> Result<bool> {
+        let current_timestamp: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
+        Ok(current_timestamp > self.setup_deadline)
+    }
+
+
+    pub fn checkpoint_pool_token_supply(&mut self, slot: u64, pool_token_supply: u64) {
+
+        // Several supply changes within the same slot are folded into a single checkpoint
+        if self.supply_checkpoints_count > 0 {
+            let last_index = ((self.supply_checkpoints_count - 1) % SUPPLY_CHECKPOINTS as u64) as usize;
+
+            if self.supply_checkpoints_slots[last_index] == slot {
+                self.supply_checkpoints[last_index] = pool_token_supply;
+                return;
+            }
+        }
+
+        // Once the buffer is full, the oldest checkpoint is overwritten
+        let next_index = (self.supply_checkpoints_count % SUPPLY_CHECKPOINTS as u64) as usize;
+
+        self.supply_checkpoints_slots[next_index] = slot;
+        self.supply_checkpoints[next_index]       = pool_token_supply;
+        self.supply_checkpoints_count += 1;
+    }
+
+
+    pub fn get_pool_token_supply_at(&self, slot: u64) -> Result<u64> {
+
+        let kept_checkpoints = self.supply_checkpoints_count.min(SUPPLY_CHECKPOINTS as u64);
+
+        // Find the latest checkpoint at or before the given slot
+        for i in 0..kept_checkpoints {
+            let index = ((self.supply_checkpoints_count - 1 - i) % SUPPLY_CHECKPOINTS as u64) as usize;
+
+            if self.supply_checkpoints_slots[index] <= slot {
+                return Ok(self.supply_checkpoints[index]);
+            }
+        }
+
+        // If no checkpoint has been pruned, the slot precedes the pool initialization
+        if self.supply_checkpoints_count <= SUPPLY_CHECKPOINTS as u64 {
+            return Ok(0);
+        }
+
+        Err(error!(ErrorCode::SupplyCheckpointPruned))
+    }
+
+
+    pub fn verify_deposit_cap(&self, asset_index: usize, asset_balance: u64, deposit_amount: u64) -> Result<()> {
+        let deposit_cap = self.deposit_caps[asset_index];
+
+        if deposit_cap != 0 && asset_balance.saturating_add(deposit_amount) > deposit_cap {
+            return Err(error!(ErrorCode::DepositCapExceeded));
+        }
+
+        Ok(())
+    }
+
+
+    pub fn verify_min_swap_amount(&self, asset_index: usize, amount: u64) -> Result<()> {
+        let min_swap_amount = self.min_swap_amounts[asset_index];
+
+        if amount < min_swap_amount {
+            return Err(error!(ErrorCode::SwapBelowMinimum));
+        }
+
+        Ok(())
+    }
+
+
+    pub fn add_volume_in(&mut self, asset_index: usize, amount: u64) {
+        self.volume_in[asset_index] = U256(self.volume_in[asset_index]).checked_add(U256::from(amount)).unwrap().0;
+    }
+
+
+    pub fn add_volume_out(&mut self, asset_index: usize, amount: u64) {
+        self.volume_out[asset_index] = U256(self.volume_out[asset_index]).checked_add(U256::from(amount)).unwrap().0;
+    }
+
+
+    // Adds the asset price since the last update times the elapsed time to the asset price accumulator. Must be called
+    // before the asset balance changes.
+    pub fn update_price_accumulator(
+        &mut self,
+        asset_index: usize,
+        asset_balance: u64,
+        current_timestamp: u64
+    ) -> Result<()> {
+
+        self.price_cumulatives_x64[asset_index] = self.get_price_cumulative_x64(asset_index, asset_balance, current_timestamp)?.0;
+        self.price_cumulatives_timestamps[asset_index] = current_timestamp;
+
+        Ok(())
+    }
+
+
+    // Returns the asset price accumulator brought up to the given timestamp, assuming the asset balance has not changed
+    // since the last update.
+    pub fn get_price_cumulative_x64(
+        &self,
+        asset_index: usize,
+        asset_balance: u64,
+        current_timestamp: u64
+    ) -> Result<U256> {
+
+        let price_cumulative_x64 = U256(self.price_cumulatives_x64[asset_index]);
+        let last_timestamp = self.price_cumulatives_timestamps[asset_index];
+
+        // Nothing to accumulate before the first update, or if the asset has no balance (price not defined)
+        if last_timestamp == 0 || asset_balance == 0 { return Ok(price_cumulative_x64) }
+
+        // The price is given by the marginal units per token, ~W/(B·ln2), computed as the approximated units of a 1 token swap
+        let price_x64 = calculation_helpers::out_swap_x64(
+            U256::one(),
+            U256::from(asset_balance),
+            U256::from(self.pool_assets_weights[asset_index]),
+            true
+        )?;
+
+        Ok(price_cumulative_x64.checked_add(
+            price_x64.checked_mul(U256::from(current_timestamp.saturating_sub(last_timestamp))).unwrap()
+        ).unwrap())
+    }
+
+
+    pub fn get_asset_count(&self) -> usize {
+        self.pool_assets_mints.iter().position(|mint| *mint == Pubkey::default()).unwrap_or(NUMASSETS)
+    }
+
+
+    // Returns the balances of the pool asset wallets, excluding the escrowed assets. One wallet must be given per pool asset.
+    pub fn get_available_balances(
+        &self,
+        swap_pool_state: &Pubkey,
+        swap_pool_asset_wallets: &[AccountInfo]
+    ) -> Result<[u64; NUMASSETS]> {
+
+        let asset_count = self.get_asset_count();
+
+        if swap_pool_asset_wallets.len() != asset_count {
+            return Err(error!(ErrorCode::InvalidRemainingAccountsCount));
+        }
+
+        let mut balances: [u64; NUMASSETS] = [0; NUMASSETS];
+        for_each_asset(asset_count, NUMASSETS, || error!(ErrorCode::AssetCountExceeded), |asset_index| {
+
+            let swap_pool_asset_wallet = &swap_pool_asset_wallets[asset_index];
+
+            // Make sure the swap_pool_asset_wallet provided matches the expected pda
+            let expected_swap_pool_asset_wallet = Pubkey::create_program_address(
+                &[
+                    &swap_pool_state.to_bytes(),
+                    &self.pool_assets_mints[asset_index].to_bytes(),
+                    POOL_ASSET_WALLET_SEED,
+                    &[self.wallets_bumps[asset_index]]
+                ],
+                &ID
+            ).unwrap();
+
+            if swap_pool_asset_wallet.key() != expected_swap_pool_asset_wallet {
+                return Err(error!(ErrorCode::InvalidSwapPoolAssetWalletAccount));
+            }
+
+            balances[asset_index] = TokenAccount::try_deserialize(&mut &swap_pool_asset_wallet.try_borrow_data()?[..])?.amount
+                .checked_sub(self.escrowed_assets[asset_index]).unwrap();
+
+            Ok(())
+        })?;
+
+        Ok(balances)
+    }
+
+
+    // Returns the units that can currently flow into the pool before hitting the units inflow limit
+    pub fn get_available_units_inflow_x64(&self, current_timestamp: u64) -> U256 {
+
+        let max_units_inflow_x64 = U256(self.max_units_inflow_x64);
+
+        // If more time has passed since the last update than DECAYRATE, the current inflow state does not matter (it has fully decayed)
+        if current_timestamp > self.current_units_inflow_timestamp + DECAYRATE {
+            return max_units_inflow_x64;
+        }
+
+        let decayed_inflow = max_units_inflow_x64.checked_mul(
+            U256::from(current_timestamp.saturating_sub(self.current_units_inflow_timestamp))
+        ).unwrap() / DECAYRATE;
+
+        let current_units_inflow_x64 = U256(self.current_units_inflow_x64).saturating_sub(decayed_inflow);
+
+        max_units_inflow_x64.saturating_sub(current_units_inflow_x64)
+    }
+
+
     pub fn update_units_inflow(
         &mut self,
         units_inflow_x64: U256,
@@ -1699,6 +3231,80 @@ impl SwapPoolState {
     }
 
 
+    // Returns the maximum units that can currently be received for the given asset without exceeding the units inflow limit
+    // nor the asset limit
+    pub fn get_max_receivable_units_x64(
+        &self,
+        to_asset_index: usize,
+        to_asset_balance: u64,
+        current_timestamp: u64
+    ) -> Result<U256> {
+
+        let mut max_units_x64 = self.get_available_units_inflow_x64(current_timestamp);
+
+        // Units that would exhaust the asset limit (if the limit is below the asset balance)
+        let asset_limit_capacity = self.get_asset_limit_capacity(to_asset_index, current_timestamp);
+
+        if asset_limit_capacity < to_asset_balance {
+            let asset_limit_units_x64 = calculation_helpers::out_swap_x64(
+                U256::from(asset_limit_capacity),
+                U256::from(to_asset_balance - asset_limit_capacity),
+                U256::from(self.pool_assets_weights[to_asset_index]),
+                false
+            )?;
+
+            if asset_limit_units_x64 < max_units_x64 { max_units_x64 = asset_limit_units_x64 }
+        }
+
+        Ok(max_units_x64)
+    }
+
+
+    // Returns the amount of the asset that can currently be withdrawn through incoming swaps (u64::MAX if there is no limit)
+    pub fn get_asset_limit_capacity(&self, asset_index: usize, current_timestamp: u64) -> u64 {
+
+        let max_asset_outflow = self.max_asset_outflows[asset_index];
+
+        if max_asset_outflow == 0 { return u64::MAX }
+
+        // The outflow decays linearly, fully decaying after DECAYRATE
+        let elapsed_time = current_timestamp.saturating_sub(self.current_asset_outflows_timestamps[asset_index]);
+        let decayed_outflow = if elapsed_time >= DECAYRATE {
+            max_asset_outflow
+        } else {
+            mul_div(max_asset_outflow, elapsed_time, DECAYRATE).unwrap()     // Smaller than max_asset_outflow, cannot overflow
+        };
+
+        let current_asset_outflow = self.current_asset_outflows[asset_index].saturating_sub(decayed_outflow);
+
+        max_asset_outflow.saturating_sub(current_asset_outflow)     // The limit may have been lowered below the current outflow
+    }
+
+
+    pub fn update_asset_outflow(
+        &mut self,
+        asset_index: usize,
+        amount: u64,
+        current_timestamp: u64
+    ) -> Result<()> {
+
+        let max_asset_outflow = self.max_asset_outflows[asset_index];
+
+        if max_asset_outflow == 0 { return Ok(()) }
+
+        let capacity = self.get_asset_limit_capacity(asset_index, current_timestamp);
+
+        if amount > capacity {
+            return Err(error!(ErrorCode::AssetLimitExceeded));
+        }
+
+        self.current_asset_outflows[asset_index]            = max_asset_outflow - capacity + amount;    // capacity <= max_asset_outflow
+        self.current_asset_outflows_timestamps[asset_index] = current_timestamp;
+
+        Ok(())
+    }
+
+
     pub fn update_liquidity_units_inflow(
         &mut self,
         pool_tokens_flow: u64,
@@ -1722,9 +3328,11 @@ impl SwapPoolState {
         }
 
         // Compute how much inflow has decayed since last update
-        let decayed_inflow = max_pool_tokens_flow.checked_mul(
-            current_timestamp.checked_sub(self.current_liquidity_inflow_timestamp).unwrap().try_into().unwrap()  // TODO checked_sub required?
-        ).unwrap() / DECAYRATE;
+        let decayed_inflow = mul_div(
+            max_pool_tokens_flow,
+            current_timestamp.checked_sub(self.current_liquidity_inflow_timestamp).unwrap(),  // TODO checked_sub required?
+            DECAYRATE
+        ).unwrap();     // Smaller than max_pool_tokens_flow, cannot overflow
 
         // If the current inflow is less then the (max allowed) decayed one
         if self.current_liquidity_inflow <= decayed_inflow {
@@ -1761,6 +3369,13 @@ pub struct SwapEscrow {
     pub fallback_wallet: Pubkey,
     pub swap_escrow_rent_payer: Pubkey,
     pub bump: u8,
+
+    // Parameters of the outgoing swap, kept for reconciliation purposes
+    pub target_chain: u64,
+    pub target_pool: Pubkey,
+    pub to_asset_index: u8,
+    pub target_withdrawer: Pubkey,
+    pub units_x64: [u64; 4]
 }
 
 impl SwapEscrow {
@@ -1770,10 +3385,141 @@ impl SwapEscrow {
         + 1     // asset_index
         + 32    // fallback_wallet
         + 32    // rent_receiver
-        + 1;    // bump
+        + 1     // bump
+        + 8     // target_chain
+        + 32    // target_pool
+        + 1     // to_asset_index
+        + 32    // target_withdrawer
+        + 32;   // units_x64
+}
+
+
+// Instruction arguments ********************************************************************************************************
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ConnectionUpdate {
+    pub chain: u64,
+    pub pool: Pubkey,
+    pub interface_program: Pubkey
+}
+
+
+
+// Query responses **************************************************************************************************************
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct VersionResponse {
+    pub contract: String,       // Crate name of the program
+    pub version: String         // Crate version of the program
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct VaultTypeResponse {
+    pub kind: String,           // "volatile" or "amplified"
+    pub schema_version: u32
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ConfigResponse {
+    pub kind: String,
+    pub pool_assets_mints: [Pubkey; NUMASSETS],
+    pub pool_assets_weights: [u64; NUMASSETS],
+    pub amplification_x64: [u64; 4],                // 1/k (x64), one for non-amplified pools
+    pub min_swap_amounts: [u64; NUMASSETS],
+    pub ibc_interface: Pubkey,
+    pub dao_authority: Pubkey,
+    pub setup_master: Pubkey                        // Default pubkey once the setup is finished
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct CumulativeVolumeResponse {
+    pub volume_in: [[u64; 4]; NUMASSETS],       // Local and outgoing swaps input (U256)
+    pub volume_out: [[u64; 4]; NUMASSETS]       // Local and incoming swaps output (U256)
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct AssetsMetadataResponse {
+    pub pool_assets_mints: [Pubkey; NUMASSETS],
+    pub decimals: [u8; NUMASSETS]
+}
+
+// Time-integrals of the from/to asset prices (units per token), both taken at 'timestamp'. The increment of an accumulator
+// between two samples divided by the elapsed time is the time-averaged price of that asset. The ratio of the from/to averages
+// is NOT the TWAP of the from/to price ratio (the mean of a ratio is not the ratio of the means), they only match if the
+// prices are constant over the sampled period.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PriceAccumulatorResponse {
+    pub from_price_cumulative_x64: [u64; 4],
+    pub to_price_cumulative_x64: [u64; 4],
+    pub timestamp: u64
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ConnectionCompatibilityResponse {
+    pub compatible: bool,
+    pub local_asset_count: u8,
+    pub expected_asset_count: u8,
+    pub mismatched_indices: Vec<u8>                 // Indices of the expected assets that do not match the pool assets
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct MinInitialLiquidityResponse {
+    pub initial_pool_tokens: u64,                   // Pool tokens minted on initialization
+    pub locked_pool_tokens: u64                     // Part of the initial pool tokens which is permanently locked
+}
+
+// Pool state after a hypothetical outgoing swap
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct SimulateSendAssetResponse {
+    pub units_x64: [u64; 4],                        // Units sent
+    pub asset_wallet_balance: u64,                  // Balance of the pool asset wallet (including the escrowed assets)
+    pub escrowed_asset_amount: u64,
+    pub current_units_inflow_x64: [u64; 4]          // Stored units inflow (before applying the decay)
+}
+
+// Describes how the minimum output of the incoming swaps is interpreted by the pool
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct MinOutReferenceResponse {
+    pub asset_swap_min_out_basis: String,           // 'to_asset': base units of the target asset (see the assets_metadata query for its decimals)
+    pub asset_swap_max_min_out: u64,                // Larger minimum outputs are rejected by the interface (the payload field is a u256)
+    pub liquidity_swap_min_out_basis: String        // 'none': liquidity swaps carry no minimum output
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct EscrowCountsResponse {
+    pub asset_escrows: u64                          // Liquidity swaps are not escrowed
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct EscrowDetailsResponse {
+    pub asset_mint: Pubkey,
+    pub amount: u64,
+    pub fallback_wallet: Pubkey,
+    pub target_chain: u64,
+    pub target_pool: Pubkey,
+    pub to_asset_index: u8,
+    pub target_withdrawer: Pubkey,
+    pub units_x64: [u64; 4]
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct WouldSendExceedLimitResponse {
+    pub exceeds_limit: bool,
+    pub remaining_capacity: u64     // Amount of the asset that can still be sent
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct MaxReceivableUnitsResponse {
+    pub units_x64: [u64; 4]
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct NormalizedBalancesResponse {
+    pub normalized_balances: [[u64; 4]; NUMASSETS]     // balance * weight (U256) of each asset, escrowed assets excluded
 }
 
 
+
 // Events ***********************************************************************************************************************
 
 // TODO add assets weights to events?
@@ -1801,6 +3547,29 @@ pub struct WithdrawEvent {
     withdrawer_pool_token_wallet: Pubkey
 }
 
+#[event]
+pub struct DonateEvent {
+    swap_pool: Pubkey,
+
+    donated_asset_amounts: [u64; NUMASSETS],
+    donor_asset_wallets: [Pubkey; NUMASSETS]
+}
+
+#[event]
+pub struct RescueTokenEvent {
+    swap_pool: Pubkey,
+    rescued_token_mint: Pubkey,
+    recipient_wallet: Pubkey,
+    amount: u64
+}
+
+#[event]
+pub struct SetDaoAuthorityEvent {
+    swap_pool: Pubkey,
+    old_dao_authority: Pubkey,
+    new_dao_authority: Pubkey
+}
+
 #[event]
 pub struct LocalSwapEvent {
     swap_pool: Pubkey,
@@ -1859,6 +3628,32 @@ pub struct OutSwapTimeoutEvent {
     escrow_nonce: u32
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SwapEscrowOutcome {
+    Acknowledged,   // The escrowed assets are released to the pool
+    TimedOut        // The escrowed assets are returned to the fallback wallet
+}
+
+#[event]
+pub struct SwapEscrowCreatedEvent {
+    swap_pool: Pubkey,
+    escrow_nonce: u32,
+
+    asset_mint: Pubkey,
+    amount: u64,
+    fallback_wallet: Pubkey,
+
+    target_chain: u64,
+    target_withdrawer: Pubkey
+}
+
+#[event]
+pub struct SwapEscrowResolvedEvent {
+    swap_pool: Pubkey,
+    escrow_nonce: u32,
+    outcome: SwapEscrowOutcome
+}
+
 #[event]
 pub struct InLiquiditySwapEvent {
     swap_pool: Pubkey,
@@ -1943,4 +3738,46 @@ pub enum ErrorCode {
     
     #[msg("The provided IBC state account does not match the expected one.")]
     InvalidIBCInterfaceAccount,
+    
+    #[msg("The provided withdrawal wallet is not owned by the specified recipient.")]
+    InvalidWithdrawalRecipient,
+    
+    #[msg("Pool assets cannot be rescued.")]
+    CannotRescuePoolAsset,
+    
+    #[msg("The vault fee must not exceed 100% (10000 bps).")]
+    InvalidVaultFee,
+    
+    #[msg("Too many connections provided for a single batch.")]
+    TooManyConnections,
+    
+    #[msg("The withdrawn amount of an asset is below the requested minimum.")]
+    WithdrawMinAmountNotFulfilled,
+    
+    #[msg("The deposit would take the pool balance of an asset above its cap.")]
+    DepositCapExceeded,
+    
+    #[msg("The setup window of the pool has expired.")]
+    SetupExpired,
+    
+    #[msg("The pool token supply checkpoint for the requested slot has been pruned.")]
+    SupplyCheckpointPruned,
+    #[msg("The asset count exceeds the maximum number of assets that can be iterated over.")]
+    AssetCountExceeded,
+    #[msg("The swap deadline has expired.")]
+    DeadlineExpired,
+    #[msg("Arithmetic overflow.")]
+    ArithmeticOverflow,
+    
+    #[msg("Donation amounts must be zero for the assets not contained in the pool.")]
+    InvalidDonationAmounts,
+    
+    #[msg("Swap amount is below the minimum allowed for the asset.")]
+    SwapBelowMinimum,
+    
+    #[msg("Swap output exceeds the asset limit.")]
+    AssetLimitExceeded,
+    
+    #[msg("The locked pool tokens cannot be rescued.")]
+    CannotRescueLockedPoolTokens,
 }
\ No newline at end of file
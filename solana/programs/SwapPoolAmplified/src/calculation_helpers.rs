@@ -2,7 +2,7 @@ use std::ops::{Shr, Shl};
 
 use anchor_lang::prelude::*;
 use shared_lib::u256::U256;
-use shared_lib::fixed_point_math_x64::{div_x64, pow_x64, inv_pow_x64};
+use shared_lib::fixed_point_math_x64::{div_x64, mul_x64, pow_x64, inv_pow_x64};
 
 const ONE_X64: U256 = U256([0, 1, 0, 0]);
 const ONE_X128: U256 = U256([0, 0, 1, 0]);
@@ -57,6 +57,9 @@ pub fn in_swap(
 ) -> Result<U256> {
     // Solves the following integral for 'y'
     // int_{Bt-y}^{Bt} WB/w dW
+    //
+    // NOTE: Units worth the whole pool balance or more (U >= WB·Bt^(1-k)) cannot be paid out, and are rejected with
+    // UnitsExceedPool rather than clamped to the pool balance.
 
     let one_minus_amp_x64 = ONE_X64.checked_sub(amplification_x64).unwrap();
 
@@ -64,6 +67,10 @@ pub fn in_swap(
         pow_x64(target_asset_balance.shl(64u8), one_minus_amp_x64).unwrap()
     ).unwrap();
 
+    if units_x64 >= intermediate_x64 {
+        return Err(error!(IntegralCalculationErrorCode::UnitsExceedPool));
+    }
+
     Ok(target_asset_balance.checked_mul(
         ONE_X64.checked_sub(inv_pow_x64(
             div_x64(intermediate_x64, intermediate_x64.checked_sub(units_x64).unwrap()).unwrap(),
@@ -72,6 +79,27 @@ pub fn in_swap(
     ).unwrap().shr(64u8))
 }
 
+// Derivative of in_swap with respect to the units at U = 0, i.e. the output received per unit (x64) by an infinitesimal
+// incoming swap. It bounds in_swap(U)/U from above, and the two converge as U shrinks.
+pub fn marginal_in_swap_x64(
+    target_asset_balance: U256,     // Bt
+    target_asset_weight: U256,      // WB
+    amplification_x64: U256         // k
+) -> Result<U256> {
+    // Bt / ((1-k) · WB · Bt^(1-k))
+
+    let one_minus_amp_x64 = ONE_X64.checked_sub(amplification_x64).unwrap();
+
+    let intermediate_x64 = target_asset_weight.checked_mul(
+        pow_x64(target_asset_balance.shl(64u8), one_minus_amp_x64).unwrap()
+    ).unwrap();
+
+    Ok(div_x64(
+        target_asset_balance.shl(64u8),
+        mul_x64(one_minus_amp_x64, intermediate_x64).unwrap()
+    ).unwrap())
+}
+
 pub fn full_swap(
     input: U256,
     source_asset_balance: U256,
@@ -109,10 +137,47 @@ pub fn full_swap(
 }
 
 
+// Deviation (in bps) of the execution price output/input from the marginal (spot) price (WA·Bt^k)/(WB·At^k)
+pub fn calc_price_impact_bps(
+    input: U256,
+    output: U256,
+    source_asset_balance: U256,     // At
+    source_asset_weight: U256,      // WA
+    target_asset_balance: U256,     // Bt
+    target_asset_weight: U256,      // WB
+    amplification_x64: U256         // k
+) -> Result<u64> {
+
+    if input.is_zero() { return Ok(0) }
+
+    // (output·WB)/(input·WA)
+    let execution_x64 = output.checked_mul(target_asset_weight).unwrap().shl(64u8).checked_div(
+        input.checked_mul(source_asset_weight).unwrap()
+    ).unwrap();
+
+    // At^k/Bt^k
+    let balances_ratio_x64 = div_x64(
+        pow_x64(source_asset_balance.shl(64u8), amplification_x64).unwrap(),
+        pow_x64(target_asset_balance.shl(64u8), amplification_x64)
+            .map_err(|_| error!(IntegralCalculationErrorCode::ArithmeticError))?      // Depleted target asset
+    ).unwrap();
+
+    let execution_to_spot_x64 = mul_x64(execution_x64, balances_ratio_x64)
+        .map_err(|_| error!(IntegralCalculationErrorCode::ArithmeticError))?;
+
+    // The execution price can round above the spot price for very small swaps
+    if execution_to_spot_x64 >= ONE_X64 { return Ok(0) }
+
+    Ok(10_000u64 - U256::from(10_000u64).checked_mul(execution_to_spot_x64).unwrap().shr(64u8).as_u64())
+}
+
+
 #[error_code]
 pub enum IntegralCalculationErrorCode {
     #[msg("Arithmetic Error. Possible overflow/underflow.")]
     ArithmeticError,
+    #[msg("The units exceed what the pool can pay out.")]
+    UnitsExceedPool,
 }
 
 
@@ -19,7 +19,10 @@ import sys
 import os
 sys.path.insert(1, os.path.join(sys.path[0], '../../../simulator'))
 
+from utils.account_utils import get_swap_pool_authority
 from utils.common_utils import SimplePoolContext, UserWallet, create_mints, create_users, setup_simple_pool_test_env
+from utils.swap_pool_utils import initialize_swap_pool_state
+from utils.token_utils import fund_accounts
 
 # Global parameters *************************************************************************************************************
 
@@ -209,6 +212,53 @@ async def polymerase_endpoint_state(
     return accountKeypair.public_key
 
 
+
+@fixture(scope="module")
+async def amplified_swap_pool_state(
+    swap_pool_amplified_program: Program,
+    swap_pool_setup_master_keypair: Keypair,
+    provider: Provider,
+    mint_authority: Keypair,
+    generic_payer: Keypair
+) -> PublicKey:
+
+    swap_pool_state_keypair = Keypair()
+
+    swap_pool_authority = get_swap_pool_authority(
+        swap_pool_amplified_program.program_id,
+        swap_pool_state_keypair.public_key,
+    )[0]
+
+    assets          = await create_mints(provider, mint_authority, 1)
+    assets_balances = [10000]
+    depositor       = (await create_users(provider, assets, generic_payer, 1))[0]
+
+    await fund_accounts(
+        provider                 = provider,
+        mints                    = assets,
+        mints_authority          = mint_authority,
+        token_accounts           = depositor.token_accounts,
+        balance                  = assets_balances,
+        delegate_authority       = swap_pool_authority,
+        delegate_balance         = assets_balances,
+        token_accounts_authority = depositor.user_keypair
+    )
+
+    await initialize_swap_pool_state(
+        swap_pool_program                       = swap_pool_amplified_program,
+        swap_pool_setup_master_keypair          = swap_pool_setup_master_keypair,
+        amplification                           = 2,
+        assets_weights                          = [1],
+        assets_balances                         = assets_balances,
+        assets_mints                            = assets,
+        deposit_wallets                         = depositor.token_accounts,
+        depositor_pool_token_wallet_authority   = depositor.user_keypair.public_key,
+        swap_pool_state_keypair                 = swap_pool_state_keypair
+    )
+
+    return swap_pool_state_keypair.public_key
+
+
 FactoryFixture = Callable[[int, int, list[int], list[int], int | None, bool], Coroutine[Any, Any, SimplePoolContext]]
 
 @fixture(scope="module")
@@ -6,13 +6,17 @@ from solana.rpc.commitment import Finalized
 
 from anchorpy import Context, Program, Provider
 
-from conftest import create_mints, create_users
+from conftest import FactoryFixture, create_mints, create_users
 from utils.account_utils import get_swap_pool_authority
-from utils.swap_interface_utils import initialize_swap_interface_state, register_swap_interface_polymerase_port
+from utils.common_utils import SOLANA_CHAIN_ID, query_version
+from utils.swap_interface_utils import initialize_swap_interface_state, list_connections, query_connection_state, register_swap_interface_polymerase_port
 from utils.verify_utils import verify_catalyst_configuration
-from utils.transaction_utils import confirm_transaction
-from utils.swap_pool_utils import POOL_MAX_ASSET_COUNT, finish_swap_pool_setup, initialize_swap_pool_state, link_swap_interface_to_swap_pool
-from utils.token_utils import fund_accounts
+from utils.transaction_utils import TxEventListener, confirm_transaction
+from utils.swap_pool_utils import POOL_MAX_ASSET_COUNT, POOL_SETUP_WINDOW, create_connection, create_connections, finish_swap_pool_setup, initialize_swap_pool_state, link_swap_interface_to_swap_pool, query_assets_metadata, query_capabilities, query_check_connection_compatibility, query_config, query_is_dao_authority, query_setup_deadline, query_vault_type, set_dao_authority
+from utils.token_utils import fund_accounts, get_mint_info
+
+
+PROGRAMS_VERSION = "0.1.0"      # As set in the programs Cargo.toml
 
 
 @given(asset_count = st.integers(min_value=0, max_value=POOL_MAX_ASSET_COUNT+1))
@@ -193,6 +197,70 @@ async def test_add_same_asset_twice(
 
 
 
+async def test_zero_asset_weight(
+    swap_pool_program: Program,
+    swap_pool_setup_master_keypair: Keypair,
+    provider: Provider,
+    mint_authority: Keypair,
+    generic_payer: Keypair
+):
+    """
+    Tests:
+        - Pools cannot be initialized with a zero weight asset (asset weights cannot be changed afterwards, hence no asset
+          can ever have a zero weight and be swapped against)
+    """
+    swap_pool_state_keypair = Keypair()
+    swap_pool_state         = swap_pool_state_keypair.public_key
+
+    swap_pool_authority = get_swap_pool_authority(
+        swap_pool_program.program_id,
+        swap_pool_state,
+    )[0]
+    
+    asset_count = 2
+
+    amplification = None
+
+    # Create new assets and mints on every run to isolate tests from each other
+    assets          = await create_mints(provider, mint_authority, asset_count)
+    assets_balances = [10000 for _ in range(asset_count)]
+    assets_weights  = [1, 0]
+
+    users           = await create_users(provider, assets, generic_payer, 1)
+
+    depositor = users[0]
+    depositor_asset_wallets               = depositor.token_accounts[:asset_count]
+    depositor_pool_token_wallet_authority = depositor.user_keypair.public_key
+    
+    await fund_accounts(
+        provider                 = provider,
+        mints                    = assets,
+        mints_authority          = mint_authority,
+        token_accounts           = depositor_asset_wallets,
+        balance                  = assets_balances,
+        delegate_authority       = swap_pool_authority,
+        delegate_balance         = assets_balances,
+        token_accounts_authority = depositor.user_keypair
+    )
+
+    with pytest.raises(Exception):
+        await initialize_swap_pool_state(
+            swap_pool_program                       = swap_pool_program,
+            swap_pool_setup_master_keypair          = swap_pool_setup_master_keypair,
+            amplification                           = amplification,
+            assets_weights                          = assets_weights,
+            assets_balances                         = assets_balances,
+            assets_mints                            = assets,
+            deposit_wallets                         = depositor_asset_wallets,
+            depositor_pool_token_wallet_authority   = depositor_pool_token_wallet_authority,
+            swap_pool_state_keypair                 = swap_pool_state_keypair
+        )
+
+    # The pool state has not been created
+    assert (await provider.connection.get_account_info(swap_pool_state)).value is None
+
+
+
 async def test_pool_setup_configurator(
     swap_pool_program: Program,
     swap_pool_setup_master_keypair: Keypair,
@@ -207,6 +275,7 @@ async def test_pool_setup_configurator(
         - Only the setup_master can finish the pool setup
         - Assets cannot be added after the pool setup is complete
         - The setup cannot be finished twice
+        - The setup deadline is within the setup window while the setup is ongoing, and reported as 0 once finished
     """
     # ! TODO Add swap_interface linkage tests
     hacker_keypair = Keypair()
@@ -259,6 +328,11 @@ async def test_pool_setup_configurator(
     )
     swap_pool_state = initialize_swap_pool_state_result.swap_pool_state
 
+    # The setup deadline is within the setup window while the setup is ongoing
+    # NOTE: the deadline boundary itself cannot be tested without warping the validator clock
+    remaining_setup_time = await query_setup_deadline(swap_pool_program, swap_pool_state)
+    assert 0 < remaining_setup_time <= POOL_SETUP_WINDOW
+
     # The pool setup cannot be finished without a swap interface
     with pytest.raises(Exception):
         await finish_swap_pool_setup(
@@ -312,6 +386,9 @@ async def test_pool_setup_configurator(
             swap_pool_setup_master_keypair
         )
 
+    # No setup time is left once the setup is finished
+    assert await query_setup_deadline(swap_pool_program, swap_pool_state) == 0
+
     
     # Verify end state
     await verify_catalyst_configuration(
@@ -356,3 +433,585 @@ async def test_swap_interface_setup(
 
 
 
+async def test_list_connections(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    generic_payer: Keypair,
+    simple_pool_context_factory: FactoryFixture
+):
+    """
+    Tests:
+        - All the connections of a swap interface can be listed
+        - The connections listing can be paginated
+    """
+
+    spc = await simple_pool_context_factory(
+        1,          # user_count
+        1,          # asset_count
+        [10000],    # pool_assets_balances
+        [1],        # pool_assets_weights
+        None,       # pool_amplification
+        True        # connect_pool_with_itself
+    )
+
+    swap_interface_state  = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+    dao_authority_keypair = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.dao_authority_keypair
+
+    # Create a second connection to a different chain
+    second_target_chain_id = SOLANA_CHAIN_ID + 1
+    second_target_pool_id  = Keypair().public_key
+
+    await create_connection(
+        target_chain_id        = second_target_chain_id,
+        target_pool_id         = second_target_pool_id,
+        target_program_id      = swap_interface_program.program_id,
+        swap_pool_program      = swap_pool_program,
+        swap_pool_state        = spc.swap_pool_state,
+        authority_keypair      = dao_authority_keypair,
+        swap_interface_program = swap_interface_program,
+        swap_interface_state   = swap_interface_state,
+        rent_payer_keypair     = generic_payer
+    )
+
+    # List all the connections
+    connections = await list_connections(swap_interface_program, swap_interface_state)
+
+    assert [(connection.chain, connection.pool) for connection in connections] == [
+        (SOLANA_CHAIN_ID, swap_interface_state),
+        (second_target_chain_id, second_target_pool_id)
+    ]
+    assert all(connection.enabled for connection in connections)
+
+    # Page through the connections
+    first_page = await list_connections(swap_interface_program, swap_interface_state, limit=1)
+    assert len(first_page) == 1
+    assert first_page[0].chain == SOLANA_CHAIN_ID
+
+    second_page = await list_connections(
+        swap_interface_program,
+        swap_interface_state,
+        start_after=(first_page[0].chain, first_page[0].pool),
+        limit=1
+    )
+    assert len(second_page) == 1
+    assert second_page[0].chain == second_target_chain_id
+    assert second_page[0].pool  == second_target_pool_id
+
+    last_page = await list_connections(
+        swap_interface_program,
+        swap_interface_state,
+        start_after=(second_page[0].chain, second_page[0].pool)
+    )
+    assert len(last_page) == 0
+
+
+
+async def test_connection_state(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    generic_payer: Keypair,
+    simple_pool_context_factory: FactoryFixture
+):
+    """
+    Tests:
+        - The connection state returns the exact counterparty pool and interface program stored on the connection
+        - Querying a connection that does not exist fails
+    """
+
+    spc = await simple_pool_context_factory(
+        1,          # user_count
+        1,          # asset_count
+        [10000],    # pool_assets_balances
+        [1],        # pool_assets_weights
+        None,       # pool_amplification
+        False       # connect_pool_with_itself
+    )
+
+    swap_interface_state  = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+    dao_authority_keypair = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.dao_authority_keypair
+
+    target_chain_id   = SOLANA_CHAIN_ID + 1
+    target_pool_id    = Keypair().public_key
+    target_program_id = Keypair().public_key
+
+    # The connection does not exist yet
+    with pytest.raises(Exception):
+        await query_connection_state(swap_interface_program, swap_interface_state, target_chain_id, target_pool_id)
+
+    await create_connection(
+        target_chain_id        = target_chain_id,
+        target_pool_id         = target_pool_id,
+        target_program_id      = target_program_id,
+        swap_pool_program      = swap_pool_program,
+        swap_pool_state        = spc.swap_pool_state,
+        authority_keypair      = dao_authority_keypair,
+        swap_interface_program = swap_interface_program,
+        swap_interface_state   = swap_interface_state,
+        rent_payer_keypair     = generic_payer
+    )
+
+    connection_state = await query_connection_state(swap_interface_program, swap_interface_state, target_chain_id, target_pool_id)
+
+    assert connection_state.chain == target_chain_id
+    assert bytes(connection_state.pool) == bytes(target_pool_id)
+    assert bytes(connection_state.connected_interface_program) == bytes(target_program_id)
+    assert connection_state.enabled
+
+
+
+async def test_create_connections(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    generic_payer: Keypair,
+    simple_pool_context_factory: FactoryFixture
+):
+    """
+    Tests:
+        - Several connections can be created at once
+        - A batch containing an invalid connection fails as a whole
+        - Batches are capped in size
+    """
+
+    spc = await simple_pool_context_factory(
+        1,          # user_count
+        1,          # asset_count
+        [10000],    # pool_assets_balances
+        [1],        # pool_assets_weights
+        None,       # pool_amplification
+        True        # connect_pool_with_itself
+    )
+
+    swap_interface_state  = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+    dao_authority_keypair = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.dao_authority_keypair
+
+    async def create_connections_batch(connections: list[tuple[int, PublicKey, PublicKey]]):
+        await create_connections(
+            connections            = connections,
+            swap_pool_program      = swap_pool_program,
+            swap_pool_state        = spc.swap_pool_state,
+            authority_keypair      = dao_authority_keypair,
+            swap_interface_program = swap_interface_program,
+            swap_interface_state   = swap_interface_state,
+            rent_payer_keypair     = generic_payer
+        )
+
+    async def listed_connections() -> list[tuple[int, PublicKey]]:
+        return [
+            (connection.chain, connection.pool)
+            for connection in await list_connections(swap_interface_program, swap_interface_state)
+        ]
+
+    # Successful batch
+    batch = [
+        (SOLANA_CHAIN_ID + 1, Keypair().public_key, swap_interface_program.program_id),
+        (SOLANA_CHAIN_ID + 2, Keypair().public_key, swap_interface_program.program_id)
+    ]
+    await create_connections_batch(batch)
+
+    expected_connections = [(SOLANA_CHAIN_ID, swap_interface_state)] + [(chain, pool) for chain, pool, _ in batch]
+    assert await listed_connections() == expected_connections
+
+    # Batch with an invalid entry (the connection already exists)
+    with pytest.raises(Exception):
+        await create_connections_batch([
+            (SOLANA_CHAIN_ID + 3, Keypair().public_key, swap_interface_program.program_id),
+            batch[0]
+        ])
+
+    assert await listed_connections() == expected_connections
+
+    # Batch too large
+    with pytest.raises(Exception):
+        await create_connections_batch([
+            (SOLANA_CHAIN_ID + 3, Keypair().public_key, swap_interface_program.program_id) for _ in range(9)
+        ])
+
+    assert await listed_connections() == expected_connections
+
+
+
+async def test_vault_type_volatile(
+    swap_pool_program: Program,
+    simple_pool_context_factory: FactoryFixture
+):
+    """
+    Tests:
+        - The volatile pool reports its vault type
+    """
+
+    spc = await simple_pool_context_factory(
+        1,          # user_count
+        1,          # asset_count
+        [10000],    # pool_assets_balances
+        [1],        # pool_assets_weights
+        None,       # pool_amplification
+        False       # connect_pool_with_itself
+    )
+
+    vault_type = await query_vault_type(swap_pool_program, spc.swap_pool_state)
+
+    assert vault_type.kind == "volatile"
+    assert vault_type.schema_version == 1
+
+
+
+async def test_vault_type_amplified(
+    swap_pool_amplified_program: Program,
+    swap_pool_setup_master_keypair: Keypair,
+    provider: Provider,
+    mint_authority: Keypair,
+    generic_payer: Keypair
+):
+    """
+    Tests:
+        - The amplified pool reports its vault type
+    """
+
+    swap_pool_state_keypair = Keypair()
+
+    swap_pool_authority = get_swap_pool_authority(
+        swap_pool_amplified_program.program_id,
+        swap_pool_state_keypair.public_key,
+    )[0]
+
+    assets          = await create_mints(provider, mint_authority, 1)
+    assets_balances = [10000]
+    depositor       = (await create_users(provider, assets, generic_payer, 1))[0]
+
+    await fund_accounts(
+        provider                 = provider,
+        mints                    = assets,
+        mints_authority          = mint_authority,
+        token_accounts           = depositor.token_accounts,
+        balance                  = assets_balances,
+        delegate_authority       = swap_pool_authority,
+        delegate_balance         = assets_balances,
+        token_accounts_authority = depositor.user_keypair
+    )
+
+    await initialize_swap_pool_state(
+        swap_pool_program                       = swap_pool_amplified_program,
+        swap_pool_setup_master_keypair          = swap_pool_setup_master_keypair,
+        amplification                           = 2,
+        assets_weights                          = [1],
+        assets_balances                         = assets_balances,
+        assets_mints                            = assets,
+        deposit_wallets                         = depositor.token_accounts,
+        depositor_pool_token_wallet_authority   = depositor.user_keypair.public_key,
+        swap_pool_state_keypair                 = swap_pool_state_keypair
+    )
+
+    vault_type = await query_vault_type(swap_pool_amplified_program, swap_pool_state_keypair.public_key)
+
+    assert vault_type.kind == "amplified"
+    assert vault_type.schema_version == 1
+
+
+
+async def test_version(
+    swap_pool_program: Program,
+    swap_pool_amplified_program: Program,
+    swap_interface_program: Program,
+    simple_pool_context_factory: FactoryFixture,
+    amplified_swap_pool_state: PublicKey
+):
+    """
+    Tests:
+        - Every program reports the crate name and version it was built from
+    """
+
+    spc = await simple_pool_context_factory(
+        1,          # user_count
+        1,          # asset_count
+        [10000],    # pool_assets_balances
+        [1],        # pool_assets_weights
+        None,       # pool_amplification
+        False       # connect_pool_with_itself
+    )
+
+    swap_interface_state = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+
+    expected_versions = [
+        (swap_pool_program,           "swap_pool_state_account", spc.swap_pool_state,       "SwapPool"),
+        (swap_pool_amplified_program, "swap_pool_state_account", amplified_swap_pool_state, "SwapPoolAmplified"),
+        (swap_interface_program,      "interface_state_account", swap_interface_state,      "IBCInterface")
+    ]
+
+    for program, state_account_name, state_account, contract in expected_versions:
+        version = await query_version(program, state_account_name, state_account)
+
+        assert version.contract == contract
+        assert version.version  == PROGRAMS_VERSION
+
+
+
+async def test_capabilities(
+    swap_pool_program: Program,
+    swap_pool_amplified_program: Program,
+    simple_pool_context_factory: FactoryFixture,
+    amplified_swap_pool_state: PublicKey
+):
+    """
+    Tests:
+        - Every pool type reports the instructions it exposes on top of the common ones
+        - The reported instructions can be invoked on the pool program
+    """
+
+    spc = await simple_pool_context_factory(
+        1,          # user_count
+        1,          # asset_count
+        [10000],    # pool_assets_balances
+        [1],        # pool_assets_weights
+        None,       # pool_amplification
+        False       # connect_pool_with_itself
+    )
+
+    expected_capabilities = [
+        (swap_pool_program,           spc.swap_pool_state,       []),
+        (swap_pool_amplified_program, amplified_swap_pool_state, ["distribute_fees"])
+    ]
+
+    for program, swap_pool_state, capabilities in expected_capabilities:
+        assert await query_capabilities(program, swap_pool_state) == capabilities
+
+        for capability in capabilities:
+            assert capability in program.rpc
+
+
+
+async def test_config(
+    swap_pool_program: Program,
+    simple_pool_context_factory: FactoryFixture
+):
+    """
+    Tests:
+        - The config query matches the pool state and the individual queries
+    """
+
+    spc = await simple_pool_context_factory(
+        1,              # user_count
+        2,              # asset_count
+        [10000, 20000], # pool_assets_balances
+        [1, 2],         # pool_assets_weights
+        None,           # pool_amplification
+        False           # connect_pool_with_itself
+    )
+
+    config     = await query_config(swap_pool_program, spc.swap_pool_state)
+    vault_type = await query_vault_type(swap_pool_program, spc.swap_pool_state)
+    state      = await swap_pool_program.account["SwapPoolState"].fetch(spc.swap_pool_state)
+
+    assert config.kind                == vault_type.kind
+    assert config.pool_assets_mints   == state.pool_assets_mints
+    assert config.pool_assets_weights == state.pool_assets_weights
+    assert config.amplification_x64   == 2**64
+    assert config.min_swap_amounts    == state.min_swap_amounts
+    assert config.ibc_interface       == state.ibc_interface
+    assert config.dao_authority       == state.dao_authority
+    assert config.setup_master        == state.setup_master
+
+
+
+async def test_is_dao_authority(
+    swap_pool_program: Program,
+    simple_pool_context_factory: FactoryFixture
+):
+    """
+    Tests:
+        - The dao authority of the pool is reported as such
+        - Other addresses are not reported as the dao authority
+    """
+
+    spc = await simple_pool_context_factory(
+        1,          # user_count
+        1,          # asset_count
+        [10000],    # pool_assets_balances
+        [1],        # pool_assets_weights
+        None,       # pool_amplification
+        False       # connect_pool_with_itself
+    )
+
+    dao_authority = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.dao_authority_keypair.public_key
+
+    assert await query_is_dao_authority(swap_pool_program, spc.swap_pool_state, dao_authority)
+    assert not await query_is_dao_authority(swap_pool_program, spc.swap_pool_state, spc.users[0].user_keypair.public_key)
+    assert not await query_is_dao_authority(swap_pool_program, spc.swap_pool_state, Keypair().public_key)
+
+
+
+async def test_set_dao_authority(
+    swap_pool_program: Program,
+    simple_pool_context_factory: FactoryFixture
+):
+    """
+    Tests:
+        - Only the dao authority can hand the pool over to a new dao authority
+        - The new dao authority is reported as such, and an event is emitted
+        - The previous dao authority loses its privileges
+    """
+
+    spc = await simple_pool_context_factory(
+        1,          # user_count
+        1,          # asset_count
+        [10000],    # pool_assets_balances
+        [1],        # pool_assets_weights
+        None,       # pool_amplification
+        False       # connect_pool_with_itself
+    )
+
+    dao_authority_keypair     = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.dao_authority_keypair
+    new_dao_authority_keypair = Keypair()
+    hacker_keypair            = Keypair()
+
+    # Only the dao authority can set a new dao authority
+    with pytest.raises(Exception):
+        await set_dao_authority(swap_pool_program, spc.swap_pool_state, hacker_keypair, new_dao_authority_keypair)
+
+    assert await query_is_dao_authority(swap_pool_program, spc.swap_pool_state, dao_authority_keypair.public_key)
+
+
+    # Set the new dao authority
+    async with TxEventListener("SetDaoAuthorityEvent") as ev_listener:
+        await set_dao_authority(swap_pool_program, spc.swap_pool_state, dao_authority_keypair, new_dao_authority_keypair)
+
+        event = (await ev_listener.get_events(swap_pool_program))[0]
+        assert event.data.swapPool == spc.swap_pool_state
+        assert event.data.oldDaoAuthority == dao_authority_keypair.public_key
+        assert event.data.newDaoAuthority == new_dao_authority_keypair.public_key
+
+    assert await query_is_dao_authority(swap_pool_program, spc.swap_pool_state, new_dao_authority_keypair.public_key)
+    assert not await query_is_dao_authority(swap_pool_program, spc.swap_pool_state, dao_authority_keypair.public_key)
+
+
+    # The previous dao authority cannot take the pool back
+    with pytest.raises(Exception):
+        await set_dao_authority(swap_pool_program, spc.swap_pool_state, dao_authority_keypair, dao_authority_keypair)
+
+
+
+async def test_query_responses_in_idl(
+    swap_pool_program: Program,
+    swap_pool_amplified_program: Program,
+    swap_interface_program: Program
+):
+    """
+    Tests:
+        - Every query response type is exported in the pools and interface IDLs, so that generated clients can decode it
+    """
+
+    query_responses = [
+        "VaultTypeResponse",
+        "VersionResponse",
+        "ConfigResponse",
+        "AssetsMetadataResponse",
+        "ConnectionCompatibilityResponse",
+        "CumulativeVolumeResponse",
+        "MinInitialLiquidityResponse",
+        "MinOutReferenceResponse",
+        "SimulateSendAssetResponse",
+        "WouldSendExceedLimitResponse",
+        "EscrowCountsResponse",
+        "EscrowDetailsResponse",
+        "MaxReceivableUnitsResponse",
+        "PriceAccumulatorResponse",
+        "NormalizedBalancesResponse"
+    ]
+
+    for program in [swap_pool_program, swap_pool_amplified_program]:
+        idl_types = [idl_type.name for idl_type in program.idl.types]
+
+        for response in query_responses:
+            assert response in idl_types
+
+    interface_query_responses = [
+        "VersionResponse",
+        "ConnectionStateResponse"
+    ]
+
+    interface_idl_types = [idl_type.name for idl_type in swap_interface_program.idl.types]
+
+    for response in interface_query_responses:
+        assert response in interface_idl_types
+
+
+
+async def test_assets_metadata(
+    swap_pool_program: Program,
+    simple_pool_context_factory: FactoryFixture
+):
+    """
+    Tests:
+        - The assets metadata query returns the pool assets mints and their decimals
+        - The asset mints must be provided in the pool order
+    """
+    provider = swap_pool_program.provider
+
+    spc = await simple_pool_context_factory(
+        1,              # user_count
+        2,              # asset_count
+        [10000, 20000], # pool_assets_balances
+        [1, 1],         # pool_assets_weights
+        None,           # pool_amplification
+        False           # connect_pool_with_itself
+    )
+
+    assets_metadata = await query_assets_metadata(swap_pool_program, spc.swap_pool_state, spc.swap_pool_assets)
+
+    assert len(assets_metadata) == 2
+    for asset, asset_metadata in zip(spc.swap_pool_assets, assets_metadata):
+        assert asset_metadata.mint     == asset
+        assert asset_metadata.decimals == (await get_mint_info(provider, asset)).decimals
+
+    with pytest.raises(Exception):
+        await query_assets_metadata(swap_pool_program, spc.swap_pool_state, spc.swap_pool_assets[::-1])
+
+
+
+async def test_check_connection_compatibility(
+    swap_pool_program: Program,
+    simple_pool_context_factory: FactoryFixture
+):
+    """
+    Tests:
+        - An expected asset list matching the pool assets ordering is reported as compatible
+        - Reordered, partial, extended or foreign asset lists are reported as incompatible, with the mismatched indices
+    """
+
+    spc = await simple_pool_context_factory(
+        1,                  # user_count
+        2,                  # asset_count
+        [10000, 20000],     # pool_assets_balances
+        [1, 1],             # pool_assets_weights
+        None,               # pool_amplification
+        False               # connect_pool_with_itself
+    )
+
+    assets = spc.swap_pool_assets
+
+    # Matching ordering
+    response = await query_check_connection_compatibility(swap_pool_program, spc.swap_pool_state, assets)
+    assert response.compatible
+    assert response.local_asset_count    == 2
+    assert response.expected_asset_count == 2
+    assert response.mismatched_indices   == []
+
+    # Swapped ordering
+    response = await query_check_connection_compatibility(swap_pool_program, spc.swap_pool_state, assets[::-1])
+    assert not response.compatible
+    assert response.mismatched_indices == [0, 1]
+
+    # Missing asset
+    response = await query_check_connection_compatibility(swap_pool_program, spc.swap_pool_state, assets[:1])
+    assert not response.compatible
+    assert response.expected_asset_count == 1
+    assert response.mismatched_indices   == []
+
+    # Extra asset
+    response = await query_check_connection_compatibility(swap_pool_program, spc.swap_pool_state, assets + [Keypair().public_key])
+    assert not response.compatible
+    assert response.mismatched_indices == [2]
+
+    # Foreign asset
+    response = await query_check_connection_compatibility(swap_pool_program, spc.swap_pool_state, [assets[0], Keypair().public_key])
+    assert not response.compatible
+    assert response.mismatched_indices == [1]
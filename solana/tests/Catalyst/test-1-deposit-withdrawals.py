@@ -1,15 +1,17 @@
 from solana.keypair import Keypair
+from solana.publickey import PublicKey
 from solana.transaction import AccountMeta
 
+import pytest
 from anchorpy import Context, Program, Provider
-from conftest import UserWallet, create_mints, create_users
+from conftest import FactoryFixture, UserWallet, create_mints, create_users
 from utils.simulator_utils import create_and_verify_catalyst_simulator
-from utils.account_utils import get_swap_pool_asset_wallet, get_swap_pool_authority
+from utils.account_utils import get_swap_pool_asset_wallet, get_swap_pool_authority, get_swap_pool_locked_token_wallet
 from utils.verify_utils import verify_catalyst_state, verify_deposit_event, verify_withdraw_event
 from utils.transaction_utils import DEFAULT_TX_COMMITMENT, TxEventListener, confirm_transaction
 
-from utils.swap_pool_utils import create_and_setup_swap_pool
-from utils.token_utils import approve, create_token_account, fund_accounts, get_account_info
+from utils.swap_pool_utils import create_and_setup_swap_pool, deposit_to_swap_pool, donate_to_swap_pool, query_deposit_cap, query_min_initial_liquidity, query_normalized_balances, query_pool_token_supply_at, query_swappable_assets, query_total_value_locked, rescue_token, set_deposit_cap, withdraw_from_swap_pool
+from utils.token_utils import approve, create_mint, create_token_account, fund_accounts, get_account_info, get_mint_info, list_token_holders, mint_to
 from spl.token.constants import TOKEN_PROGRAM_ID
 
 
@@ -362,6 +364,7 @@ async def test_withdrawals(
     async with TxEventListener("WithdrawEvent") as ev_listener:
         tx_result = await swap_pool_program.rpc["withdraw"](
             withdrawn_pool_tokens_base,
+            None,
             ctx=Context(
                 accounts={
                     "swap_pool_state_account": swap_pool_state,
@@ -400,7 +403,9 @@ async def test_withdrawals(
         create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_token_mint,
         withdrawer_pool_token_wallet
     )
-    assert withdrawer_pool_token_account_info.amount == catalyst_simulator.pool_tokens_distribution_i[withdrawer_keypair.public_key]
+    # NOTE: the simulator credits all the initial pool tokens to the pool creator, including the ones locked by the pool
+    locked_pool_tokens = (await query_min_initial_liquidity(swap_pool_program, swap_pool_state)).locked_pool_tokens
+    assert withdrawer_pool_token_account_info.amount + locked_pool_tokens == catalyst_simulator.pool_tokens_distribution_i[withdrawer_keypair.public_key]
                     
     # Make sure the assets have been transferred from the pool to the withdrawer
     for asset_index in range(asset_count):
@@ -423,3 +428,755 @@ async def test_withdrawals(
         )
         assert(withdrawer_account_info.amount == asset_withdrawal_amounts[asset_index])
 
+
+
+async def test_donations(
+    swap_pool_program: Program,
+    mint_authority: Keypair,
+    provider: Provider,
+    simple_pool_context_factory: FactoryFixture
+):
+    """
+    Tests:
+        - Donated assets are transferred to the pool without minting pool tokens
+        - The spot prices of the pool shift according to the donated amounts
+        - Donations for assets not contained in the pool are rejected
+        - Donations can only be taken from wallets owned by the donor
+    """
+
+    pool_assets_balances = [10000, 10000]
+    pool_assets_weights  = [1, 1]
+
+    spc = await simple_pool_context_factory(
+        2,                      # user_count
+        2,                      # asset_count
+        pool_assets_balances,
+        pool_assets_weights,
+        None,                   # pool_amplification
+        False                   # connect_pool_with_itself
+    )
+
+    swap_pool_token_mint    = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_token_mint
+    swap_pool_asset_wallets = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+
+    donor            = spc.users[1]
+    donated_amounts  = [1000, 0]
+
+    await fund_accounts(
+        provider                 = provider,
+        mints                    = spc.swap_pool_assets,
+        mints_authority          = mint_authority,
+        token_accounts           = donor.token_accounts,
+        balance                  = donated_amounts
+    )
+
+    initial_pool_token_supply = (await get_mint_info(provider, swap_pool_token_mint)).supply
+
+    donate_result = await donate_to_swap_pool(
+        swap_pool_program,
+        donated_amounts,
+        spc.swap_pool_assets,
+        donor.token_accounts,
+        donor.user_keypair,
+        spc.swap_pool_state
+    )
+
+    assert donate_result.donate_event.data.swapPool == spc.swap_pool_state
+    assert donate_result.donate_event.data.donatedAssetAmounts == donated_amounts + [0]
+
+    # No pool tokens must have been minted
+    assert (await get_mint_info(provider, swap_pool_token_mint)).supply == initial_pool_token_supply
+
+    # Verify the pool balances and the resulting spot price of the first asset in terms of the second one
+    pool_balances = [
+        (await get_account_info(provider, asset, swap_pool_asset_wallets[i])).amount
+        for i, asset in enumerate(spc.swap_pool_assets)
+    ]
+    assert pool_balances == [balance + donated_amounts[i] for i, balance in enumerate(pool_assets_balances)]
+
+    initial_spot_price = (pool_assets_balances[1] / pool_assets_weights[1]) / (pool_assets_balances[0] / pool_assets_weights[0])
+    spot_price         = (pool_balances[1] / pool_assets_weights[1]) / (pool_balances[0] / pool_assets_weights[0])
+    assert spot_price < initial_spot_price
+
+    # Donating an asset which is not contained in the pool must fail
+    with pytest.raises(Exception):
+        await donate_to_swap_pool(
+            swap_pool_program,
+            [0, 0, 1000],
+            spc.swap_pool_assets,
+            donor.token_accounts,
+            donor.user_keypair,
+            spc.swap_pool_state
+        )
+
+    # Donating from a wallet not owned by the donor must fail, even if the wallet has approved the pool authority
+    victim = spc.users[0]
+
+    await fund_accounts(
+        provider                 = provider,
+        mints                    = spc.swap_pool_assets,
+        mints_authority          = mint_authority,
+        token_accounts           = victim.token_accounts,
+        balance                  = donated_amounts,
+        delegate_authority       = spc.swap_pool_authority,
+        delegate_balance         = donated_amounts,
+        token_accounts_authority = victim.user_keypair
+    )
+
+    with pytest.raises(Exception):
+        await donate_to_swap_pool(
+            swap_pool_program,
+            donated_amounts,
+            spc.swap_pool_assets,
+            victim.token_accounts,
+            donor.user_keypair,
+            spc.swap_pool_state
+        )
+
+
+
+async def test_withdrawals_to_recipient(
+    swap_pool_program: Program,
+    provider: Provider,
+    simple_pool_context_factory: FactoryFixture
+):
+    """
+    Tests:
+        - Withdrawn assets can be sent to the withdrawer's own wallets
+        - Withdrawn assets can be sent to a third party's wallets
+        - Withdrawals fail if the provided wallets are not owned by the specified recipient
+    """
+
+    spc = await simple_pool_context_factory(
+        2,                      # user_count
+        2,                      # asset_count
+        [10000, 10000],         # pool_assets_balances
+        [1, 1],                 # pool_assets_weights
+        None,                   # pool_amplification
+        False                   # connect_pool_with_itself
+    )
+
+    swap_pool_token_mint         = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_token_mint
+    withdrawer                   = spc.users[0]
+    withdrawer_pool_token_wallet = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.depositor_pool_token_wallet_keypair.public_key
+    third_party                  = spc.users[1]
+
+    withdrawn_pool_tokens = 1000
+
+    async def withdraw_to(recipient: PublicKey, recipient_asset_wallets: list[PublicKey]):
+        return await withdraw_from_swap_pool(
+            swap_pool_program,
+            withdrawn_pool_tokens,
+            recipient,
+            spc.swap_pool_assets,
+            recipient_asset_wallets,
+            withdrawer_pool_token_wallet,
+            withdrawer.user_keypair,
+            spc.swap_pool_state,
+            swap_pool_token_mint,
+            spc.swap_pool_authority
+        )
+
+    async def get_balances(wallets: list[PublicKey]) -> list[int]:
+        return [
+            (await get_account_info(provider, asset, wallets[i])).amount
+            for i, asset in enumerate(spc.swap_pool_assets)
+        ]
+
+    # Withdraw to self
+    withdrawer_balances_before = await get_balances(withdrawer.token_accounts)
+    withdraw_result = await withdraw_to(withdrawer.user_keypair.public_key, withdrawer.token_accounts)
+    withdrawer_balances_after  = await get_balances(withdrawer.token_accounts)
+
+    withdrawn_amounts = withdraw_result.withdraw_event.data.withdrawnAssetAmounts[:len(spc.swap_pool_assets)]
+    assert all(amount > 0 for amount in withdrawn_amounts)
+    assert [after - before for before, after in zip(withdrawer_balances_before, withdrawer_balances_after)] == withdrawn_amounts
+
+    # Withdraw to a third party
+    third_party_balances_before = await get_balances(third_party.token_accounts)
+    withdraw_result = await withdraw_to(third_party.user_keypair.public_key, third_party.token_accounts)
+    third_party_balances_after  = await get_balances(third_party.token_accounts)
+
+    withdrawn_amounts = withdraw_result.withdraw_event.data.withdrawnAssetAmounts[:len(spc.swap_pool_assets)]
+    assert withdraw_result.withdraw_event.data.withdrawerAssetWallets[:len(spc.swap_pool_assets)] == third_party.token_accounts
+    assert [after - before for before, after in zip(third_party_balances_before, third_party_balances_after)] == withdrawn_amounts
+
+    # The wallets must be owned by the specified recipient
+    with pytest.raises(Exception):
+        await withdraw_to(third_party.user_keypair.public_key, withdrawer.token_accounts)
+
+
+
+async def test_withdrawals_balanced(
+    swap_pool_program: Program,
+    provider: Provider,
+    simple_pool_context_factory: FactoryFixture
+):
+    """
+    Tests:
+        - Balanced withdrawals return the assets in the proportion of the pool balances
+        - Balanced withdrawals fail if any of the withdrawn amounts is below the requested minimum
+    """
+
+    pool_assets_balances = [10000, 30000]
+
+    spc = await simple_pool_context_factory(
+        1,                      # user_count
+        2,                      # asset_count
+        pool_assets_balances,
+        [1, 1],                 # pool_assets_weights
+        None,                   # pool_amplification
+        False                   # connect_pool_with_itself
+    )
+
+    swap_pool_token_mint         = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_token_mint
+    withdrawer                   = spc.users[0]
+    withdrawer_pool_token_wallet = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.depositor_pool_token_wallet_keypair.public_key
+
+    withdrawn_pool_tokens = 1000
+
+    async def withdraw_balanced(min_amounts: list[int]):
+        return await withdraw_from_swap_pool(
+            swap_pool_program,
+            withdrawn_pool_tokens,
+            None,
+            spc.swap_pool_assets,
+            withdrawer.token_accounts,
+            withdrawer_pool_token_wallet,
+            withdrawer.user_keypair,
+            spc.swap_pool_state,
+            swap_pool_token_mint,
+            spc.swap_pool_authority,
+            min_amounts=min_amounts
+        )
+
+    pool_tokens_supply = (await get_mint_info(provider, swap_pool_token_mint)).supply
+    expected_amounts   = [balance * withdrawn_pool_tokens // pool_tokens_supply for balance in pool_assets_balances]
+
+    # Minimums above the expected amounts
+    with pytest.raises(Exception):
+        await withdraw_balanced([expected_amounts[0], expected_amounts[1] + 1])
+
+    # Minimums fulfilled
+    withdraw_result   = await withdraw_balanced([amount - 1 for amount in expected_amounts])
+    withdrawn_amounts = withdraw_result.withdraw_event.data.withdrawnAssetAmounts[:len(spc.swap_pool_assets)]
+
+    assert all(withdrawn >= expected - 1 for withdrawn, expected in zip(withdrawn_amounts, expected_amounts))
+
+    # The withdrawn amounts match the pool composition (within rounding)
+    assert abs(withdrawn_amounts[1] - 3 * withdrawn_amounts[0]) <= 3
+
+
+
+async def test_normalized_balances(
+    swap_pool_program: Program,
+    simple_pool_context_factory: FactoryFixture
+):
+    """
+    Tests:
+        - The normalized balances (balance * weight) of a pool with skewed weights are reported
+        - The pool asset wallets must be provided
+    """
+
+    pool_assets_balances = [3000, 1000, 500]
+    pool_assets_weights  = [1, 3, 10]
+
+    spc = await simple_pool_context_factory(
+        1,                      # user_count
+        3,                      # asset_count
+        pool_assets_balances,
+        pool_assets_weights,
+        None,                   # pool_amplification
+        False                   # connect_pool_with_itself
+    )
+
+    swap_pool_asset_wallets = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+
+    normalized_balances = await query_normalized_balances(swap_pool_program, spc.swap_pool_state, swap_pool_asset_wallets)
+
+    assert normalized_balances == [balance * weight for balance, weight in zip(pool_assets_balances, pool_assets_weights)]
+
+    # Invalid pool asset wallets
+    with pytest.raises(Exception):
+        await query_normalized_balances(swap_pool_program, spc.swap_pool_state, spc.users[0].token_accounts)
+
+    with pytest.raises(Exception):
+        await query_normalized_balances(swap_pool_program, spc.swap_pool_state, swap_pool_asset_wallets[:-1])
+
+
+
+async def test_total_value_locked(
+    swap_pool_program: Program,
+    simple_pool_context_factory: FactoryFixture
+):
+    """
+    Tests:
+        - The TVL of a balanced pool is the sum of the balances of its assets
+        - The assets are valued at the pool's spot price, which is given by the weights ratio for a balanced pool
+        - The reference asset must be a pool asset
+        - The unused asset slots cannot be used as the reference asset
+    """
+
+    pool_assets_balances = [1000, 1000, 1000]
+    pool_assets_weights  = [1, 1, 1]
+
+    spc = await simple_pool_context_factory(
+        1,                      # user_count
+        3,                      # asset_count
+        pool_assets_balances,
+        pool_assets_weights,
+        None,                   # pool_amplification
+        False                   # connect_pool_with_itself
+    )
+
+    swap_pool_asset_wallets = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+
+    for reference_asset in spc.swap_pool_assets:
+        tvl = await query_total_value_locked(swap_pool_program, spc.swap_pool_state, swap_pool_asset_wallets, reference_asset)
+        assert tvl == sum(pool_assets_balances)
+
+    # Skewed weights: the holdings of each asset are valued proportionally to its weight (the spot price of the asset i in
+    # terms of the asset r is (Wi/Bi)/(Wr/Br)), hence the asset 1 holdings are worth twice the asset 0 holdings
+    pool_assets_balances = [2000, 1000]
+    pool_assets_weights  = [1, 2]
+
+    spc = await simple_pool_context_factory(
+        1,                      # user_count
+        2,                      # asset_count
+        pool_assets_balances,
+        pool_assets_weights,
+        None,                   # pool_amplification
+        False                   # connect_pool_with_itself
+    )
+
+    swap_pool_asset_wallets = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+
+    assert await query_total_value_locked(swap_pool_program, spc.swap_pool_state, swap_pool_asset_wallets, spc.swap_pool_assets[0]) == 2000 + 2*2000
+    assert await query_total_value_locked(swap_pool_program, spc.swap_pool_state, swap_pool_asset_wallets, spc.swap_pool_assets[1]) == 1000//2 + 1000
+
+    # The reference asset is not contained in the pool
+    with pytest.raises(Exception):
+        await query_total_value_locked(swap_pool_program, spc.swap_pool_state, swap_pool_asset_wallets, Keypair().public_key)
+
+    # The pool has an unused asset slot, which is identified by the default pubkey
+    with pytest.raises(Exception):
+        await query_total_value_locked(swap_pool_program, spc.swap_pool_state, swap_pool_asset_wallets, PublicKey(0))
+
+
+
+async def test_swappable_assets(
+    swap_pool_program: Program,
+    provider: Provider,
+    simple_pool_context_factory: FactoryFixture
+):
+    """
+    Tests:
+        - The swappable assets from a given asset are the other pool assets with a nonzero balance
+        - The pool cannot be drained by its depositors (the pool tokens locked on initialization keep some of every asset)
+    """
+
+    spc = await simple_pool_context_factory(
+        1,                      # user_count
+        3,                      # asset_count
+        [1000, 2000, 3000],     # pool_assets_balances
+        [1, 1, 1],              # pool_assets_weights
+        None,                   # pool_amplification
+        False                   # connect_pool_with_itself
+    )
+
+    swap_pool_token_mint         = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_token_mint
+    swap_pool_asset_wallets      = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+    withdrawer                   = spc.users[0]
+    withdrawer_pool_token_wallet = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.depositor_pool_token_wallet_keypair.public_key
+
+    async def swappable_assets(from_asset: PublicKey) -> list[PublicKey]:
+        return await query_swappable_assets(swap_pool_program, spc.swap_pool_state, swap_pool_asset_wallets, from_asset)
+
+    for from_asset in spc.swap_pool_assets:
+        assert await swappable_assets(from_asset) == [asset for asset in spc.swap_pool_assets if asset != from_asset]
+
+    # The from asset must be a pool asset
+    with pytest.raises(Exception):
+        await swappable_assets(Keypair().public_key)
+
+    # Withdraw all the depositor's pool tokens
+    await withdraw_from_swap_pool(
+        swap_pool_program,
+        (await get_account_info(provider, swap_pool_token_mint, withdrawer_pool_token_wallet)).amount,
+        None,
+        spc.swap_pool_assets,
+        withdrawer.token_accounts,
+        withdrawer_pool_token_wallet,
+        withdrawer.user_keypair,
+        spc.swap_pool_state,
+        swap_pool_token_mint,
+        spc.swap_pool_authority
+    )
+
+    for from_asset in spc.swap_pool_assets:
+        assert await swappable_assets(from_asset) == [asset for asset in spc.swap_pool_assets if asset != from_asset]
+
+
+
+async def test_min_initial_liquidity(
+    swap_pool_program: Program,
+    provider: Provider,
+    simple_pool_context_factory: FactoryFixture
+):
+    """
+    Tests:
+        - The initial pool tokens are minted to the depositor, except for the locked ones
+        - The locked pool tokens are held by the pool and cannot be withdrawn
+    """
+
+    spc = await simple_pool_context_factory(
+        1,                      # user_count
+        2,                      # asset_count
+        [1000, 1000],           # pool_assets_balances
+        [1, 1],                 # pool_assets_weights
+        None,                   # pool_amplification
+        False                   # connect_pool_with_itself
+    )
+
+    liquidity = await query_min_initial_liquidity(swap_pool_program, spc.swap_pool_state)
+    assert 0 < liquidity.locked_pool_tokens < liquidity.initial_pool_tokens
+
+    swap_pool_token_mint          = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_token_mint
+    depositor_pool_token_wallet   = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.depositor_pool_token_wallet_keypair.public_key
+    swap_pool_locked_token_wallet = get_swap_pool_locked_token_wallet(swap_pool_program.program_id, spc.swap_pool_state)[0]
+
+    assert (await get_mint_info(provider, swap_pool_token_mint)).supply == liquidity.initial_pool_tokens
+
+    depositor_pool_tokens    = (await get_account_info(provider, swap_pool_token_mint, depositor_pool_token_wallet)).amount
+    locked_token_wallet_info = await get_account_info(provider, swap_pool_token_mint, swap_pool_locked_token_wallet)
+
+    assert depositor_pool_tokens           == liquidity.initial_pool_tokens - liquidity.locked_pool_tokens
+    assert locked_token_wallet_info.amount == liquidity.locked_pool_tokens
+
+    # The locked pool tokens are owned by the pool authority (rescue_token rejects the locked wallet, see test_rescue_token)
+    assert locked_token_wallet_info.owner == spc.swap_pool_authority
+
+
+
+async def test_deposit_cap(
+    swap_pool_program: Program,
+    provider: Provider,
+    mint_authority: Keypair,
+    generic_payer: Keypair,
+    simple_pool_context_factory: FactoryFixture
+):
+    """
+    Tests:
+        - Only the dao authority can set the deposit cap of an asset
+        - The deposit cap can be queried
+        - Deposits up to the cap succeed, deposits beyond it fail
+        - Removing the cap allows further deposits
+    """
+
+    spc = await simple_pool_context_factory(
+        2,                      # user_count
+        2,                      # asset_count
+        [1000, 1000],           # pool_assets_balances
+        [1, 1],                 # pool_assets_weights
+        None,                   # pool_amplification
+        False                   # connect_pool_with_itself
+    )
+
+    dao_authority_keypair = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.dao_authority_keypair
+    swap_pool_token_mint  = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_token_mint
+    capped_asset          = spc.swap_pool_assets[0]
+    depositor             = spc.users[1]
+
+    deposit_cap = 1500
+
+    assert await query_deposit_cap(swap_pool_program, spc.swap_pool_state, capped_asset) == 0
+
+    with pytest.raises(Exception):
+        await set_deposit_cap(swap_pool_program, spc.swap_pool_state, capped_asset, deposit_cap, Keypair())
+
+    await set_deposit_cap(swap_pool_program, spc.swap_pool_state, capped_asset, deposit_cap, dao_authority_keypair)
+
+    assert await query_deposit_cap(swap_pool_program, spc.swap_pool_state, capped_asset) == deposit_cap
+    assert await query_deposit_cap(swap_pool_program, spc.swap_pool_state, spc.swap_pool_assets[1]) == 0
+
+    for asset, wallet in zip(spc.swap_pool_assets, depositor.token_accounts):
+        await mint_to(provider, wallet, asset, mint_authority, 1000)
+
+    depositor_pool_token_wallet = await create_token_account(provider, swap_pool_token_mint, depositor.user_keypair.public_key, generic_payer)
+
+    async def deposit(pool_tokens_amount: int):
+        await deposit_to_swap_pool(
+            swap_pool_program,
+            pool_tokens_amount,
+            spc.swap_pool_assets,
+            depositor.token_accounts,
+            depositor.user_keypair,
+            depositor_pool_token_wallet,
+            spc.swap_pool_state,
+            swap_pool_token_mint,
+            spc.swap_pool_authority
+        )
+
+    # Deposit up to the cap (the pool holds 1000 of each asset for 1000000 pool tokens)
+    await deposit(500000)
+
+    swap_pool_asset_wallet = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets[0]
+    assert (await get_account_info(provider, capped_asset, swap_pool_asset_wallet)).amount == deposit_cap
+
+    # Deposit beyond the cap
+    with pytest.raises(Exception):
+        await deposit(1000)
+
+    # Remove the cap
+    await set_deposit_cap(swap_pool_program, spc.swap_pool_state, capped_asset, 0, dao_authority_keypair)
+
+    await deposit(1000)
+
+
+
+async def test_list_pool_token_holders(
+    swap_pool_program: Program,
+    provider: Provider,
+    mint_authority: Keypair,
+    generic_payer: Keypair,
+    simple_pool_context_factory: FactoryFixture
+):
+    """
+    Tests:
+        - All the pool token holders can be listed with their balances, ordered by wallet
+        - The holders listing can be paginated
+    """
+
+    spc = await simple_pool_context_factory(
+        2,                      # user_count
+        2,                      # asset_count
+        [1000, 1000],           # pool_assets_balances
+        [1, 1],                 # pool_assets_weights
+        None,                   # pool_amplification
+        False                   # connect_pool_with_itself
+    )
+
+    swap_pool_token_mint        = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_token_mint
+    creator_pool_token_wallet   = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.depositor_pool_token_wallet_keypair.public_key
+    locked_pool_token_wallet    = get_swap_pool_locked_token_wallet(swap_pool_program.program_id, spc.swap_pool_state)[0]
+    depositor                   = spc.users[1]
+
+    # Add a third holder
+    for asset, wallet in zip(spc.swap_pool_assets, depositor.token_accounts):
+        await mint_to(provider, wallet, asset, mint_authority, 10)
+
+    depositor_pool_token_wallet = await create_token_account(provider, swap_pool_token_mint, depositor.user_keypair.public_key, generic_payer)
+
+    await deposit_to_swap_pool(
+        swap_pool_program,
+        10000,
+        spc.swap_pool_assets,
+        depositor.token_accounts,
+        depositor.user_keypair,
+        depositor_pool_token_wallet,
+        spc.swap_pool_state,
+        swap_pool_token_mint,
+        spc.swap_pool_authority
+    )
+
+    # Wallets without balance are not listed
+    await create_token_account(provider, swap_pool_token_mint, Keypair().public_key, generic_payer)
+
+    # List all the holders
+    liquidity = await query_min_initial_liquidity(swap_pool_program, spc.swap_pool_state)
+
+    expected_holders = sorted(
+        [
+            (creator_pool_token_wallet, liquidity.initial_pool_tokens - liquidity.locked_pool_tokens),
+            (locked_pool_token_wallet, liquidity.locked_pool_tokens),
+            (depositor_pool_token_wallet, 10000)
+        ],
+        key=lambda holder: bytes(holder[0])
+    )
+
+    holders = await list_token_holders(provider, swap_pool_token_mint)
+    assert [(holder.wallet, holder.balance) for holder in holders] == expected_holders
+
+    # Page through the holders
+    start_after = None
+    for expected_holder in expected_holders:
+        page = await list_token_holders(provider, swap_pool_token_mint, start_after=start_after, limit=1)
+        assert [(holder.wallet, holder.balance) for holder in page] == [expected_holder]
+        start_after = page[0].wallet
+
+    assert await list_token_holders(provider, swap_pool_token_mint, start_after=start_after) == []
+
+
+
+async def test_rescue_token(
+    swap_pool_program: Program,
+    mint_authority: Keypair,
+    simple_pool_context_factory: FactoryFixture
+):
+    """
+    Tests:
+        - Only the dao authority can rescue tokens
+        - Tokens that are not pool assets held by the pool authority can be rescued
+        - Pool assets cannot be rescued
+        - The locked pool tokens cannot be rescued
+    """
+    provider = swap_pool_program.provider
+
+    spc = await simple_pool_context_factory(
+        1,          # user_count
+        1,          # asset_count
+        [10000],    # pool_assets_balances
+        [1],        # pool_assets_weights
+        None,       # pool_amplification
+        False       # connect_pool_with_itself
+    )
+
+    dao_authority_keypair   = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.dao_authority_keypair
+    swap_pool_asset_wallets = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+
+    # Tokens accidentally sent to a wallet owned by the pool authority
+    foreign_mint   = await create_mint(provider, mint_authority.public_key)
+    foreign_wallet = await create_token_account(provider, foreign_mint, spc.swap_pool_authority)
+    foreign_amount = 1234
+
+    await mint_to(provider, foreign_wallet, foreign_mint, mint_authority, foreign_amount)
+
+    recipient_wallet = await create_token_account(provider, foreign_mint, spc.users[0].user_keypair.public_key)
+
+    # Not the dao authority
+    with pytest.raises(Exception):
+        await rescue_token(
+            swap_pool_program,
+            spc.swap_pool_state,
+            spc.swap_pool_authority,
+            foreign_wallet,
+            recipient_wallet,
+            Keypair()
+        )
+
+    await rescue_token(
+        swap_pool_program,
+        spc.swap_pool_state,
+        spc.swap_pool_authority,
+        foreign_wallet,
+        recipient_wallet,
+        dao_authority_keypair
+    )
+
+    assert (await get_account_info(provider, foreign_mint, foreign_wallet)).amount == 0
+    assert (await get_account_info(provider, foreign_mint, recipient_wallet)).amount == foreign_amount
+
+    # Pool asset
+    pool_asset_recipient_wallet = await create_token_account(provider, spc.swap_pool_assets[0], spc.users[0].user_keypair.public_key)
+
+    with pytest.raises(Exception):
+        await rescue_token(
+            swap_pool_program,
+            spc.swap_pool_state,
+            spc.swap_pool_authority,
+            swap_pool_asset_wallets[0],
+            pool_asset_recipient_wallet,
+            dao_authority_keypair
+        )
+
+    assert (await get_account_info(provider, spc.swap_pool_assets[0], swap_pool_asset_wallets[0])).amount == 10000
+
+    # Locked pool tokens
+    swap_pool_token_mint          = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_token_mint
+    swap_pool_locked_token_wallet = get_swap_pool_locked_token_wallet(swap_pool_program.program_id, spc.swap_pool_state)[0]
+    pool_token_recipient_wallet   = await create_token_account(provider, swap_pool_token_mint, spc.users[0].user_keypair.public_key)
+
+    locked_pool_tokens = (await get_account_info(provider, swap_pool_token_mint, swap_pool_locked_token_wallet)).amount
+
+    with pytest.raises(Exception):
+        await rescue_token(
+            swap_pool_program,
+            spc.swap_pool_state,
+            spc.swap_pool_authority,
+            swap_pool_locked_token_wallet,
+            pool_token_recipient_wallet,
+            dao_authority_keypair
+        )
+
+    assert (await get_account_info(provider, swap_pool_token_mint, swap_pool_locked_token_wallet)).amount == locked_pool_tokens
+    assert (await get_account_info(provider, swap_pool_token_mint, pool_token_recipient_wallet)).amount == 0
+
+
+
+async def test_pool_token_supply_at(
+    swap_pool_program: Program,
+    provider: Provider,
+    mint_authority: Keypair,
+    generic_payer: Keypair,
+    simple_pool_context_factory: FactoryFixture
+):
+    """
+    Tests:
+        - The pool token supply is checkpointed on initialization, deposits and withdrawals
+        - The supply at a slot is the one of the latest checkpoint at or before that slot
+        - The supply before the pool initialization is 0
+    """
+
+    spc = await simple_pool_context_factory(
+        2,                      # user_count
+        2,                      # asset_count
+        [1000, 1000],           # pool_assets_balances
+        [1, 1],                 # pool_assets_weights
+        None,                   # pool_amplification
+        False                   # connect_pool_with_itself
+    )
+
+    swap_pool_token_mint = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_token_mint
+    depositor            = spc.users[1]
+
+    initial_supply = (await get_mint_info(provider, swap_pool_token_mint)).supply
+
+    initialized_slot = (await provider.connection.get_slot()).value
+    assert await query_pool_token_supply_at(swap_pool_program, spc.swap_pool_state, initialized_slot) == initial_supply
+    assert await query_pool_token_supply_at(swap_pool_program, spc.swap_pool_state, 0) == 0
+
+
+    # Deposit
+    for asset, wallet in zip(spc.swap_pool_assets, depositor.token_accounts):
+        await mint_to(provider, wallet, asset, mint_authority, 1000)
+
+    depositor_pool_token_wallet = await create_token_account(provider, swap_pool_token_mint, depositor.user_keypair.public_key, generic_payer)
+
+    await deposit_to_swap_pool(
+        swap_pool_program,
+        500000,
+        spc.swap_pool_assets,
+        depositor.token_accounts,
+        depositor.user_keypair,
+        depositor_pool_token_wallet,
+        spc.swap_pool_state,
+        swap_pool_token_mint,
+        spc.swap_pool_authority
+    )
+
+    deposit_slot = (await provider.connection.get_slot()).value
+    assert await query_pool_token_supply_at(swap_pool_program, spc.swap_pool_state, deposit_slot) == initial_supply + 500000
+
+
+    # Withdraw
+    await withdraw_from_swap_pool(
+        swap_pool_program,
+        200000,
+        None,
+        spc.swap_pool_assets,
+        depositor.token_accounts,
+        depositor_pool_token_wallet,
+        depositor.user_keypair,
+        spc.swap_pool_state,
+        swap_pool_token_mint,
+        spc.swap_pool_authority
+    )
+
+    withdraw_slot = (await provider.connection.get_slot()).value
+    assert await query_pool_token_supply_at(swap_pool_program, spc.swap_pool_state, withdraw_slot) == initial_supply + 300000
+    assert (await get_mint_info(provider, swap_pool_token_mint)).supply == initial_supply + 300000
+
+
+    # Past checkpoints are still available
+    assert await query_pool_token_supply_at(swap_pool_program, spc.swap_pool_state, initialized_slot) == initial_supply
+    assert await query_pool_token_supply_at(swap_pool_program, spc.swap_pool_state, deposit_slot) == initial_supply + 500000
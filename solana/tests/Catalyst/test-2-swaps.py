@@ -1,14 +1,21 @@
+import asyncio
+import math
+from datetime import datetime, timedelta
+import pytest
 from solana.keypair import Keypair
+from solana.publickey import PublicKey
 from solana.transaction import AccountMeta
 from solana.system_program import SYS_PROGRAM_ID
 
-from anchorpy import Context, Program
-from conftest import FactoryFixture, generic_payer
+from anchorpy import Context, Program, Provider
+from conftest import FactoryFixture, create_mints, create_users, generic_payer
+from utils.account_utils import get_swap_pool_authority
 from utils.common_utils import SOLANA_CHAIN_ID
 
-from utils.swap_pool_utils import perform_cross_chain_swap, perform_local_swap
+from utils.swap_interface_utils import encode_asset_swap_payload, query_default_timeout, query_is_blocklisted, query_send_asset_packet_size, set_default_timeout, set_max_units_per_packet, set_pool_blocklisted
+from utils.swap_pool_utils import POOL_MAX_ASSET_COUNT, initialize_swap_pool_state, donate_to_swap_pool, perform_cross_chain_swap, perform_local_swap, query_asset_limit_capacity, query_calc_local_swap_with_fee, query_calc_receive_asset, query_cumulative_volume, query_depth_to_impact, query_escrow_counts, query_escrow_details, query_guaranteed_receive_asset, query_marginal_receive_rate, query_max_receivable_units, query_min_out_reference, query_min_swap_amount, query_price_accumulator, query_price_impact, query_simulate_send_asset, query_would_send_exceed_limit, set_max_asset_outflow, set_min_swap_amount
 from utils.transaction_utils import TxEventListener, confirm_transaction
-from utils.token_utils import get_account_info, mint_to
+from utils.token_utils import fund_accounts, get_account_info, mint_to
 from utils.verify_utils import u256_array_to_int, verify_catalyst_state, verify_in_swap_event
 from spl.token.constants import TOKEN_PROGRAM_ID
 
@@ -305,10 +312,29 @@ async def test_self_cross_chain_swap(
 
     await verify_catalyst_state(swap_pool_program, swap_pool_state, catalyst_simulator)
 
+    # The swap is escrowed until it is acknowledged or timed out
+    assert await query_escrow_counts(swap_pool_program, swap_pool_state) == 1
+
     # Compare simulated out_swap units with the actual ones (from out_swap event)
     out_swap_units = u256_array_to_int(cross_chain_swap_result.out_swap_event.data.withdrawnPoolUnitsX64)
     assert sim_out_swap_units == out_swap_units
 
+    # The escrow creation is logged
+    swap_escrow_created_event = cross_chain_swap_result.swap_escrow_created_event
+    assert swap_escrow_created_event.data.swapPool         == swap_pool_state
+    assert swap_escrow_created_event.data.escrowNonce      == source_swap_id
+    assert swap_escrow_created_event.data.assetMint        == swapper_input_asset
+    assert swap_escrow_created_event.data.amount           == swapper_input_asset_balance
+    assert swap_escrow_created_event.data.targetChain      == target_chain_id
+    assert swap_escrow_created_event.data.targetWithdrawer == swapper_output_asset_wallet
+
+    # The packet targets a pool connected through the same interface program, it is flagged as loopback
+    loopback_packet_event = cross_chain_swap_result.loopback_packet_event
+    assert loopback_packet_event is not None
+    assert loopback_packet_event.data.interface  == swap_interface_state
+    assert loopback_packet_event.data.chain      == target_chain_id
+    assert loopback_packet_event.data.targetPool == target_pool_id
+
 
 
     # Step 2: Perform cross-chain swap execute => receive => in_swap
@@ -317,6 +343,9 @@ async def test_self_cross_chain_swap(
     # Fetch payload
     data = await polymerase_sender_program.account["IbcData"].fetch(cross_chain_swap_result.ibc_data)
 
+    # The estimated packet size must match the size of the actual payload
+    assert len(data.payload) == await query_send_asset_packet_size(swap_interface_program, swap_interface_state)
+
     authority = Keypair() #not used for now
 
     async with TxEventListener("InSwapEvent") as ev_listener:
@@ -390,33 +419,53 @@ async def test_self_cross_chain_swap(
 
     # Step 3: Trigger ibc ack on source chain (simulates polymer chain endpoint contract)
     swap_escrow = cross_chain_swap_result.swap_escrow
-    ack_result = await swap_interface_program.rpc["on_acknowledgement_packet"](
-        data.payload,
-        ctx=Context(
-            accounts={
-                "port_registration": port_registration,
-                "interface_state_account": swap_interface_state,
-                "swap_pool": swap_pool_state,
-                "swap_pool_program": swap_pool_program.program_id,
-                "swap_pool_authority": swap_pool_authority,
-                "token_program": TOKEN_PROGRAM_ID,
-                "system_program": SYS_PROGRAM_ID
-            },
-            remaining_accounts=[
-                AccountMeta(swapper_output_asset,          is_signer=False, is_writable=False ), # output_asset_mint
-                AccountMeta(swapper_output_asset_wallet,   is_signer=False, is_writable=True  ), # output_asset_wallet
-                AccountMeta(swap_pool_output_asset_wallet, is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
-                AccountMeta(swap_escrow,                   is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
-                AccountMeta(generic_payer.public_key,      is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
-            ]
+
+    async def acknowledge(payload: bytes):
+        return await swap_interface_program.rpc["on_acknowledgement_packet"](
+            payload,
+            ctx=Context(
+                accounts={
+                    "port_registration": port_registration,
+                    "interface_state_account": swap_interface_state,
+                    "swap_pool": swap_pool_state,
+                    "swap_pool_program": swap_pool_program.program_id,
+                    "swap_pool_authority": swap_pool_authority,
+                    "token_program": TOKEN_PROGRAM_ID,
+                    "system_program": SYS_PROGRAM_ID
+                },
+                remaining_accounts=[
+                    AccountMeta(swapper_output_asset,          is_signer=False, is_writable=False ), # output_asset_mint
+                    AccountMeta(swapper_output_asset_wallet,   is_signer=False, is_writable=True  ), # output_asset_wallet
+                    AccountMeta(swap_pool_output_asset_wallet, is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
+                    AccountMeta(swap_escrow,                   is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
+                    AccountMeta(generic_payer.public_key,      is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
+                ]
+            )
         )
-    )
-    await confirm_transaction(provider, ack_result)
+
+    # Corrupt (truncated, empty or of unknown context) packets must error, leaving the escrow open
+    for corrupt_payload in [data.payload[:-1], b"", b"\x09" + data.payload[1:]]:
+        with pytest.raises(Exception):
+            await confirm_transaction(provider, await acknowledge(corrupt_payload))
+
+    assert await query_escrow_counts(swap_pool_program, swap_pool_state) == 1
+
+    async with TxEventListener("SwapEscrowResolvedEvent") as ev_listener:
+        ack_result = await acknowledge(data.payload)
+        await confirm_transaction(provider, ack_result)
+
+        # The escrow resolution is logged
+        swap_escrow_resolved_event = (await ev_listener.get_events(swap_pool_program))[0]
+        assert swap_escrow_resolved_event.data.swapPool    == swap_pool_state
+        assert swap_escrow_resolved_event.data.escrowNonce == source_swap_id
+        assert swap_escrow_resolved_event.data.outcome.__class__.__name__ == "Acknowledged"
 
     catalyst_simulator.out_swap_ack(source_swap_id)
 
     await verify_catalyst_state(swap_pool_program, swap_pool_state, catalyst_simulator)
 
+    assert await query_escrow_counts(swap_pool_program, swap_pool_state) == 0
+
     # Compare the current state of the output asset destination wallet
     swapper_output_account_info_after_swap_ack = await get_account_info(
         provider,
@@ -543,10 +592,24 @@ async def test_self_cross_chain_swap_timeout(
 
     await verify_catalyst_state(swap_pool_program, swap_pool_state, catalyst_simulator)
 
+    # The swap is escrowed until it is acknowledged or timed out
+    assert await query_escrow_counts(swap_pool_program, swap_pool_state) == 1
+
     # Compare simulated out_swap units with the actual ones (from out_swap event)
     out_swap_units = u256_array_to_int(cross_chain_swap_result.out_swap_event.data.withdrawnPoolUnitsX64)
     assert sim_out_swap_units == out_swap_units
 
+    # The escrow holds the parameters of the swap
+    escrow_details = await query_escrow_details(swap_pool_program, swap_pool_state, source_swap_id)
+    assert escrow_details.asset_mint        == swapper_input_asset
+    assert escrow_details.amount            == swapper_input_asset_balance
+    assert escrow_details.fallback_wallet   == swapper_input_asset_wallet
+    assert escrow_details.target_chain      == target_chain_id
+    assert escrow_details.target_pool       == target_pool_id
+    assert escrow_details.to_asset_index    == swapper_output_asset_index
+    assert escrow_details.target_withdrawer == swapper_output_asset_wallet
+    assert escrow_details.units_x64         == out_swap_units
+
 
 
     # Skip Step 2: Do not perform cross-chain swap execute => receive => in_swap
@@ -554,6 +617,9 @@ async def test_self_cross_chain_swap_timeout(
     # Fetch payload
     data = await polymerase_sender_program.account["IbcData"].fetch(cross_chain_swap_result.ibc_data)
 
+    # The estimated packet size must match the size of the actual payload
+    assert len(data.payload) == await query_send_asset_packet_size(swap_interface_program, swap_interface_state)
+
     # Compare the current state of the output asset destination wallet
     swapper_output_account_info_after_out_swap = await get_account_info(
         provider,
@@ -572,41 +638,1849 @@ async def test_self_cross_chain_swap_timeout(
 
     # Step 3: Trigger ibc timeout on source chain (simulates polymer chain endpoint contract)
     swap_escrow = cross_chain_swap_result.swap_escrow
-    ack_result = await swap_interface_program.rpc["on_timeout_packet"](
+
+    async def timeout(payload: bytes):
+        return await swap_interface_program.rpc["on_timeout_packet"](
+            payload,
+            ctx=Context(
+                accounts={
+                    "port_registration": port_registration,
+                    "interface_state_account": swap_interface_state,
+                    "swap_pool": swap_pool_state,
+                    "swap_pool_program": swap_pool_program.program_id,
+                    "swap_pool_authority": swap_pool_authority,
+                    "token_program": TOKEN_PROGRAM_ID,
+                    "system_program": SYS_PROGRAM_ID
+                },
+                remaining_accounts=[
+                    AccountMeta(swapper_output_asset,          is_signer=False, is_writable=False ), # output_asset_mint
+                    AccountMeta(swapper_output_asset_wallet,   is_signer=False, is_writable=True  ), # output_asset_wallet
+                    AccountMeta(swap_pool_output_asset_wallet, is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
+                    AccountMeta(swap_escrow,                   is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
+                    AccountMeta(generic_payer.public_key,      is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
+                ]
+            )
+        )
+
+    # Corrupt (truncated, empty or of unknown context) packets must error, leaving the escrow open and the assets escrowed
+    for corrupt_payload in [data.payload[:-1], b"", b"\x09" + data.payload[1:]]:
+        with pytest.raises(Exception):
+            await confirm_transaction(provider, await timeout(corrupt_payload))
+
+    assert await query_escrow_counts(swap_pool_program, swap_pool_state) == 1
+
+    async with TxEventListener("SwapEscrowResolvedEvent") as ev_listener:
+        ack_result = await timeout(data.payload)
+        await confirm_transaction(provider, ack_result)
+
+        # The escrow resolution is logged
+        swap_escrow_resolved_event = (await ev_listener.get_events(swap_pool_program))[0]
+        assert swap_escrow_resolved_event.data.escrowNonce == source_swap_id
+        assert swap_escrow_resolved_event.data.outcome.__class__.__name__ == "TimedOut"
+
+    catalyst_simulator.out_swap_timeout(source_swap_id)
+
+    await verify_catalyst_state(swap_pool_program, swap_pool_state, catalyst_simulator)
+
+    assert await query_escrow_counts(swap_pool_program, swap_pool_state) == 0
+
+    # The escrow is closed once resolved
+    with pytest.raises(Exception):
+        await query_escrow_details(swap_pool_program, swap_pool_state, source_swap_id)
+
+    # Compare the current state of the output asset destination wallet
+    swapper_output_account_info_after_swap_timeout = await get_account_info(
+        provider,
+        swapper_output_asset,
+        swapper_output_asset_wallet
+    )
+
+    before_balance         = swapper_output_account_info_before_swap.amount
+    after_timeout_balance  = swapper_output_account_info_after_swap_timeout.amount
+
+    assert before_balance == after_timeout_balance   # Timeout must return assets
+
+
+
+async def test_cross_chain_swap_fallback_wallet(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    polymerase_emulator_setup_master_keypair: Keypair,
+    polymerase_sender_program: Program,
+    mint_authority: Keypair,
+    generic_payer: Keypair,
+    simple_pool_context_factory: FactoryFixture,
+):
+    """
+    Tests:
+        - The fallback wallet must hold the swapped asset
+        - Timed out swaps refund the specified fallback wallet, not the sender
+    """
+
+    provider = swap_pool_program.provider
+
+    spc = await simple_pool_context_factory(
+        2,                      # user_count
+        2,                      # asset_count
+        [1000, 3000],           # pool_assets_balances
+        [1, 1],                 # pool_assets_weights
+        None,                   # pool_amplification
+        True                    # connect_pool_with_itself
+    )
+
+    swapper         = spc.users[1]
+    fallback_user   = spc.users[0]
+    input_asset     = spc.swap_pool_assets[0]
+    input_wallet    = swapper.token_accounts[0]
+    fallback_wallet = fallback_user.token_accounts[0]
+    swap_amount     = 500
+
+    swap_pool_input_asset_wallet = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets[0]
+    swap_interface_state         = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+    port_registration            = spc.create_and_setup_swap_pool_result.register_polymerase_port_result.polymerase_port
+
+    assert spc.create_connection_result is not None
+    connection_state = spc.create_connection_result.connection_state
+
+    await mint_to(provider, input_wallet, input_asset, mint_authority, swap_amount)
+
+    async def cross_chain_swap(fallback: PublicKey, escrow_nonce: int):
+        return await perform_cross_chain_swap(
+            swap_amount,
+            input_asset,
+            input_wallet,
+            swapper.user_keypair,
+            SOLANA_CHAIN_ID,
+            swap_interface_state,           # Target pool
+            0,                              # Output asset index
+            swapper.token_accounts[0],      # Destination
+            escrow_nonce,
+            generic_payer,
+            swap_pool_program,
+            spc.swap_pool_state,
+            swap_pool_input_asset_wallet,
+            spc.swap_pool_authority,
+            swap_interface_program,
+            swap_interface_state,
+            connection_state,
+            polymerase_sender_program,
+            polymerase_emulator_setup_master_keypair,
+            fallback_wallet=fallback
+        )
+
+    # A fallback wallet of a different asset is rejected
+    with pytest.raises(Exception):
+        await cross_chain_swap(fallback_user.token_accounts[1], 0)
+
+    cross_chain_swap_result = await cross_chain_swap(fallback_wallet, 0)
+    assert cross_chain_swap_result.swap_escrow_created_event.data.fallbackWallet == fallback_wallet
+
+    swapper_balance_before_timeout  = (await get_account_info(provider, input_asset, input_wallet)).amount
+    fallback_balance_before_timeout = (await get_account_info(provider, input_asset, fallback_wallet)).amount
+
+    # Time out the swap
+    data = await polymerase_sender_program.account["IbcData"].fetch(cross_chain_swap_result.ibc_data)
+
+    tx = await swap_interface_program.rpc["on_timeout_packet"](
         data.payload,
         ctx=Context(
             accounts={
                 "port_registration": port_registration,
                 "interface_state_account": swap_interface_state,
-                "swap_pool": swap_pool_state,
+                "swap_pool": spc.swap_pool_state,
                 "swap_pool_program": swap_pool_program.program_id,
-                "swap_pool_authority": swap_pool_authority,
+                "swap_pool_authority": spc.swap_pool_authority,
                 "token_program": TOKEN_PROGRAM_ID,
                 "system_program": SYS_PROGRAM_ID
             },
             remaining_accounts=[
-                AccountMeta(swapper_output_asset,          is_signer=False, is_writable=False ), # output_asset_mint
-                AccountMeta(swapper_output_asset_wallet,   is_signer=False, is_writable=True  ), # output_asset_wallet
-                AccountMeta(swap_pool_output_asset_wallet, is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
-                AccountMeta(swap_escrow,                   is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
-                AccountMeta(generic_payer.public_key,      is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
+                AccountMeta(input_asset,                         is_signer=False, is_writable=False ), # escrowed asset mint
+                AccountMeta(fallback_wallet,                     is_signer=False, is_writable=True  ), # fallback wallet
+                AccountMeta(swap_pool_input_asset_wallet,        is_signer=False, is_writable=True  ), # swap pool asset wallet
+                AccountMeta(cross_chain_swap_result.swap_escrow, is_signer=False, is_writable=True  ), # swap escrow
+                AccountMeta(generic_payer.public_key,            is_signer=False, is_writable=True  ), # swap escrow rent payer
             ]
         )
     )
-    await confirm_transaction(provider, ack_result)
+    await confirm_transaction(provider, tx)
 
-    catalyst_simulator.out_swap_timeout(source_swap_id)
+    # The escrowed assets are returned to the fallback wallet
+    assert (await get_account_info(provider, input_asset, input_wallet)).amount == swapper_balance_before_timeout
+    assert (await get_account_info(provider, input_asset, fallback_wallet)).amount == fallback_balance_before_timeout + swap_amount
 
-    await verify_catalyst_state(swap_pool_program, swap_pool_state, catalyst_simulator)
 
-    # Compare the current state of the output asset destination wallet
-    swapper_output_account_info_after_swap_timeout = await get_account_info(
-        provider,
-        swapper_output_asset,
-        swapper_output_asset_wallet
+
+async def test_simulate_send_asset(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    polymerase_emulator_setup_master_keypair: Keypair,
+    polymerase_sender_program: Program,
+    mint_authority: Keypair,
+    generic_payer: Keypair,
+    simple_pool_context_factory: FactoryFixture,
+):
+    """
+    Tests:
+        - The simulated pool state after an outgoing swap matches the actual one after performing the swap
+        - The simulation does not modify the pool state
+    """
+
+    provider = swap_pool_program.provider
+
+    spc = await simple_pool_context_factory(
+        2,                      # user_count
+        2,                      # asset_count
+        [1000, 3000],           # pool_assets_balances
+        [1, 1],                 # pool_assets_weights
+        None,                   # pool_amplification
+        True                    # connect_pool_with_itself
     )
 
-    before_balance         = swapper_output_account_info_before_swap.amount
-    after_timeout_balance  = swapper_output_account_info_after_swap_timeout.amount
+    swapper      = spc.users[1]
+    input_asset  = spc.swap_pool_assets[0]
+    input_wallet = swapper.token_accounts[0]
+    swap_amount  = 500
 
-    assert before_balance == after_timeout_balance   # Timeout must return assets
+    swap_pool_asset_wallets = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+    swap_interface_state    = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+
+    assert spc.create_connection_result is not None
+    connection_state = spc.create_connection_result.connection_state
+
+    await mint_to(provider, input_wallet, input_asset, mint_authority, swap_amount)
+
+    simulated_state = await query_simulate_send_asset(swap_pool_program, spc.swap_pool_state, swap_pool_asset_wallets, input_asset, swap_amount)
+
+    # The simulation does not modify the pool state
+    assert (await swap_pool_program.account["SwapPoolState"].fetch(spc.swap_pool_state)).escrowed_assets[0] == 0
+    assert simulated_state == await query_simulate_send_asset(swap_pool_program, spc.swap_pool_state, swap_pool_asset_wallets, input_asset, swap_amount)
+
+    cross_chain_swap_result = await perform_cross_chain_swap(
+        swap_amount,
+        input_asset,
+        input_wallet,
+        swapper.user_keypair,
+        SOLANA_CHAIN_ID,
+        swap_interface_state,           # Target pool
+        0,                              # Output asset index
+        swapper.token_accounts[0],      # Destination
+        0,                              # Escrow nonce
+        generic_payer,
+        swap_pool_program,
+        spc.swap_pool_state,
+        swap_pool_asset_wallets[0],
+        spc.swap_pool_authority,
+        swap_interface_program,
+        swap_interface_state,
+        connection_state,
+        polymerase_sender_program,
+        polymerase_emulator_setup_master_keypair
+    )
+
+    state = await swap_pool_program.account["SwapPoolState"].fetch(spc.swap_pool_state)
+
+    assert simulated_state.units_x64                == u256_array_to_int(cross_chain_swap_result.out_swap_event.data.withdrawnPoolUnitsX64)
+    assert simulated_state.asset_wallet_balance     == (await get_account_info(provider, input_asset, swap_pool_asset_wallets[0])).amount
+    assert simulated_state.escrowed_asset_amount    == state.escrowed_assets[0]
+    assert simulated_state.current_units_inflow_x64 == u256_array_to_int(state.current_units_inflow_x64)
+
+
+
+async def test_self_cross_chain_swap_units_cap(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    swap_interface_setup_master_keypair: Keypair,
+    polymerase_emulator_setup_master_keypair: Keypair,
+    polymerase_sender_program: Program,
+    polymerase_receiver_program: Program,
+    mint_authority: Keypair,
+    generic_payer: Keypair,
+    simple_pool_context_factory: FactoryFixture,
+):
+    """
+    Tests:
+        - Received packets with units above the interface cap are rejected, and a UnitsCapExceededEvent is emitted
+        - Received packets with units at or below the interface cap are accepted
+    """
+    provider = swap_pool_program.provider
+
+    spc = await simple_pool_context_factory(
+        2,              # user_count
+        1,              # asset_count
+        [10000],        # pool_assets_balances
+        [1],            # pool_assets_weights
+        None,           # pool_amplification
+        True            # connect_pool_with_itself
+    )
+
+    swap_pool_state      = spc.swap_pool_state
+    swap_pool_authority  = spc.swap_pool_authority
+    swap_asset           = spc.swap_pool_assets[0]
+    swapper              = spc.users[1]
+    swapper_asset_wallet = swapper.token_accounts[0]
+
+    swap_pool_asset_wallet = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets[0]
+    swap_interface_state   = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+    port_registration      = spc.create_and_setup_swap_pool_result.register_polymerase_port_result.polymerase_port
+
+    assert spc.create_connection_result is not None
+    connection_state = spc.create_connection_result.connection_state
+
+    swap_amounts = [500, 100]
+
+    await mint_to(provider, swapper_asset_wallet, swap_asset, mint_authority, sum(swap_amounts))
+
+    async def out_swap(amount: int, source_swap_id: int) -> tuple[int, bytes]:
+        cross_chain_swap_result = await perform_cross_chain_swap(
+            amount,
+            swap_asset,
+            swapper_asset_wallet,
+            swapper.user_keypair,
+            SOLANA_CHAIN_ID,
+            swap_interface_state,
+            0,
+            swapper_asset_wallet,
+            source_swap_id,
+            generic_payer,
+            swap_pool_program,
+            swap_pool_state,
+            swap_pool_asset_wallet,
+            swap_pool_authority,
+            swap_interface_program,
+            swap_interface_state,
+            connection_state,
+            polymerase_sender_program,
+            polymerase_emulator_setup_master_keypair
+        )
+
+        units = u256_array_to_int(cross_chain_swap_result.out_swap_event.data.withdrawnPoolUnitsX64)
+        data  = await polymerase_sender_program.account["IbcData"].fetch(cross_chain_swap_result.ibc_data)
+
+        return units, data.payload
+
+    async def receive(payload: bytes):
+        authority = Keypair() #not used for now
+
+        rpc_result = await polymerase_receiver_program.rpc["on_receive"](
+            swap_interface_state,
+            "",
+            "",
+            "",
+            "",
+            1,
+            payload,
+            0,
+            0,
+            ctx=Context(
+                accounts={
+                    "port_registration": port_registration,
+                    "dapp_program": swap_interface_program.program_id,
+                    "authority": authority.public_key
+                },
+                remaining_accounts=[
+                    AccountMeta(swap_interface_state,          is_signer=False, is_writable=False ), # interface_state_account
+                    AccountMeta(swap_pool_state,               is_signer=False, is_writable=True  ), # swap_pool
+                    AccountMeta(swap_pool_program.program_id,  is_signer=False, is_writable=False ), # swap_pool_program
+                    AccountMeta(TOKEN_PROGRAM_ID,              is_signer=False, is_writable=False ), # token_program
+                    AccountMeta(connection_state,              is_signer=False, is_writable=False ), # connection_state_account
+                    AccountMeta(swap_asset,                    is_signer=False, is_writable=False ), # output_asset_mint
+                    AccountMeta(swapper_asset_wallet,          is_signer=False, is_writable=True  ), # output_asset_wallet
+                    AccountMeta(swap_pool_asset_wallet,        is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
+                    AccountMeta(swap_pool_authority,           is_signer=False, is_writable=False ), # swap_pool_authority
+                ],
+                signers=[authority]
+            )
+        )
+        await confirm_transaction(provider, rpc_result)
+
+    units, payload = await out_swap(swap_amounts[0], 0)
+
+    # Above the cap
+    await set_max_units_per_packet(swap_interface_program, swap_interface_state, units - 1, swap_interface_setup_master_keypair)
+
+    async with TxEventListener("UnitsCapExceededEvent") as ev_listener:
+        with pytest.raises(Exception):
+            await receive(payload)
+
+        units_cap_exceeded_events = await ev_listener.get_events(swap_interface_program)
+
+    assert len(units_cap_exceeded_events) == 1
+    assert units_cap_exceeded_events[0].data.interface == swap_interface_state
+    assert u256_array_to_int(units_cap_exceeded_events[0].data.unitsX64) == units
+    assert u256_array_to_int(units_cap_exceeded_events[0].data.maxUnitsX64) == units - 1
+
+    # At the cap
+    await set_max_units_per_packet(swap_interface_program, swap_interface_state, units, swap_interface_setup_master_keypair)
+    await receive(payload)
+
+    # Below the cap
+    smaller_units, smaller_payload = await out_swap(swap_amounts[1], 1)
+    assert smaller_units < units
+
+    await receive(smaller_payload)
+
+    # Disable the cap
+    await set_max_units_per_packet(swap_interface_program, swap_interface_state, None, swap_interface_setup_master_keypair)
+
+    interface_state = await swap_interface_program.account["CrossChainSwapInterfaceState"].fetch(swap_interface_state)
+    assert interface_state.max_units_per_packet is None
+
+
+
+async def test_recv_packet_fail_reasons(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    swap_interface_setup_master_keypair: Keypair,
+    simple_pool_context_factory: FactoryFixture,
+):
+    """
+    Tests:
+        - Received packets that cannot be decoded are tagged with the 'Decode' fail reason
+        - Truncated and empty received packets are tagged with the 'Decode' fail reason
+        - Received packets that fail validation are tagged with the 'Validate' fail reason
+        - Received packets above the units cap are tagged with the 'UnitsCapExceeded' fail reason
+        - Received packets rejected by the pool fail within the pool itself
+    """
+    provider = swap_pool_program.provider
+
+    spc = await simple_pool_context_factory(
+        2,              # user_count
+        1,              # asset_count
+        [10000],        # pool_assets_balances
+        [1],            # pool_assets_weights
+        None,           # pool_amplification
+        True            # connect_pool_with_itself
+    )
+
+    swap_asset             = spc.swap_pool_assets[0]
+    swapper_asset_wallet   = spc.users[1].token_accounts[0]
+    swap_pool_asset_wallet = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets[0]
+    swap_interface_state   = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+    port_registration      = spc.create_and_setup_swap_pool_result.register_polymerase_port_result.polymerase_port
+
+    assert spc.create_connection_result is not None
+    connection_state = spc.create_connection_result.connection_state
+
+    async def receive(payload: bytes) -> list:
+        authority = Keypair() #not used for now
+
+        async with TxEventListener() as ev_listener:
+            with pytest.raises(Exception):
+                tx = await swap_interface_program.rpc["on_recv_packet"](
+                    payload,
+                    ctx=Context(
+                        accounts={
+                            "port_registration": port_registration,
+                            "polymerase_authority": authority.public_key,
+                            "interface_state_account": swap_interface_state,
+                            "swap_pool": spc.swap_pool_state,
+                            "swap_pool_program": swap_pool_program.program_id,
+                            "token_program": TOKEN_PROGRAM_ID,
+                            "connection_state_account": connection_state
+                        },
+                        remaining_accounts=[
+                            AccountMeta(swap_asset,              is_signer=False, is_writable=False ), # output_asset_mint
+                            AccountMeta(swapper_asset_wallet,    is_signer=False, is_writable=True  ), # output_asset_wallet
+                            AccountMeta(swap_pool_asset_wallet,  is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
+                            AccountMeta(spc.swap_pool_authority, is_signer=False, is_writable=False ), # swap_pool_authority
+                        ],
+                        signers=[authority]
+                    )
+                )
+                await confirm_transaction(provider, tx)
+
+            return [event for event in await ev_listener.get_events(swap_interface_program) if event.name == "RecvPacketFailEvent"]
+
+    def build_payload(destination: PublicKey = swapper_asset_wallet, min_output: int = 0) -> bytes:
+        return encode_asset_swap_payload(
+            source_pool        = swap_interface_state,
+            target_pool        = swap_interface_state,
+            destination        = destination,
+            units_x64          = 2**64,
+            target_asset_index = 0,
+            min_output         = min_output
+        )
+
+    # Decode: invalid context
+    events = await receive(b"\x09" + build_payload()[1:])
+    assert len(events) == 1
+    assert events[0].data.failReason.__class__.__name__ == "Decode"
+
+    # Decode: invalid payload length
+    events = await receive(build_payload()[:-1])
+    assert len(events) == 1
+    assert events[0].data.failReason.__class__.__name__ == "Decode"
+
+    # Decode: packets truncated before the end of the target pool are rejected instead of panicking
+    for truncated_payload in [build_payload()[:64], build_payload()[:1], b""]:
+        events = await receive(truncated_payload)
+        assert len(events) == 1
+        assert events[0].data.failReason.__class__.__name__ == "Decode"
+
+    # Validate: the destination does not match the provided output asset wallet
+    events = await receive(build_payload(destination=Keypair().public_key))
+    assert len(events) == 1
+    assert events[0].data.interface == swap_interface_state
+    assert events[0].data.failReason.__class__.__name__ == "Validate"
+
+    # UnitsCapExceeded: the packet units (2**64) exceed the cap
+    await set_max_units_per_packet(swap_interface_program, swap_interface_state, 2**64 - 1, swap_interface_setup_master_keypair)
+
+    events = await receive(build_payload())
+    assert len(events) == 1
+    assert events[0].data.failReason.__class__.__name__ == "UnitsCapExceeded"
+
+    await set_max_units_per_packet(swap_interface_program, swap_interface_state, None, swap_interface_setup_master_keypair)
+
+    # Pool: the minimum output cannot be fulfilled (the transaction is aborted within the pool)
+    events = await receive(build_payload(min_output=2**64-1))
+    assert len(events) == 0
+
+
+
+async def test_blocklisted_pools(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    swap_interface_setup_master_keypair: Keypair,
+    simple_pool_context_factory: FactoryFixture,
+):
+    """
+    Tests:
+        - Packets sent by a blocklisted pool are rejected with the 'Blocklisted' fail reason
+        - Packets sent by an unblocked pool are no longer rejected by the interface
+        - Only the configurator can blocklist pools
+    """
+    provider = swap_pool_program.provider
+
+    spc = await simple_pool_context_factory(
+        2,              # user_count
+        1,              # asset_count
+        [10000],        # pool_assets_balances
+        [1],            # pool_assets_weights
+        None,           # pool_amplification
+        True            # connect_pool_with_itself
+    )
+
+    swap_asset             = spc.swap_pool_assets[0]
+    swapper_asset_wallet   = spc.users[1].token_accounts[0]
+    swap_pool_asset_wallet = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets[0]
+    swap_interface_state   = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+    port_registration      = spc.create_and_setup_swap_pool_result.register_polymerase_port_result.polymerase_port
+
+    assert spc.create_connection_result is not None
+    connection_state = spc.create_connection_result.connection_state
+
+    # The pool is connected with itself, hence it is the source pool of the packets
+    source_pool = swap_interface_state
+
+    # NOTE: The minimum output cannot be fulfilled, so that the packets that make it through the interface are aborted within the pool
+    payload = encode_asset_swap_payload(
+        source_pool        = source_pool,
+        target_pool        = swap_interface_state,
+        destination        = swapper_asset_wallet,
+        units_x64          = 2**64,
+        target_asset_index = 0,
+        min_output         = 2**64-1
+    )
+
+    async def receive() -> list:
+        authority = Keypair() #not used for now
+
+        async with TxEventListener() as ev_listener:
+            with pytest.raises(Exception):
+                tx = await swap_interface_program.rpc["on_recv_packet"](
+                    payload,
+                    ctx=Context(
+                        accounts={
+                            "port_registration": port_registration,
+                            "polymerase_authority": authority.public_key,
+                            "interface_state_account": swap_interface_state,
+                            "swap_pool": spc.swap_pool_state,
+                            "swap_pool_program": swap_pool_program.program_id,
+                            "token_program": TOKEN_PROGRAM_ID,
+                            "connection_state_account": connection_state
+                        },
+                        remaining_accounts=[
+                            AccountMeta(swap_asset,              is_signer=False, is_writable=False ), # output_asset_mint
+                            AccountMeta(swapper_asset_wallet,    is_signer=False, is_writable=True  ), # output_asset_wallet
+                            AccountMeta(swap_pool_asset_wallet,  is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
+                            AccountMeta(spc.swap_pool_authority, is_signer=False, is_writable=False ), # swap_pool_authority
+                        ],
+                        signers=[authority]
+                    )
+                )
+                await confirm_transaction(provider, tx)
+
+            return [event for event in await ev_listener.get_events(swap_interface_program) if event.name == "RecvPacketFailEvent"]
+
+    assert not await query_is_blocklisted(swap_interface_program, swap_interface_state, source_pool)
+
+    # Only the configurator can blocklist pools
+    with pytest.raises(Exception):
+        await set_pool_blocklisted(swap_interface_program, swap_interface_state, source_pool, True, Keypair())
+
+    # Blocklist the pool
+    await set_pool_blocklisted(swap_interface_program, swap_interface_state, source_pool, True, swap_interface_setup_master_keypair)
+    assert await query_is_blocklisted(swap_interface_program, swap_interface_state, source_pool)
+
+    events = await receive()
+    assert len(events) == 1
+    assert events[0].data.failReason.__class__.__name__ == "Blocklisted"
+
+    # Unblock the pool
+    await set_pool_blocklisted(swap_interface_program, swap_interface_state, source_pool, False, swap_interface_setup_master_keypair)
+    assert not await query_is_blocklisted(swap_interface_program, swap_interface_state, source_pool)
+
+    events = await receive()
+    assert len(events) == 0
+
+
+
+async def test_default_timeout(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    swap_interface_setup_master_keypair: Keypair,
+    polymerase_emulator_setup_master_keypair: Keypair,
+    polymerase_sender_program: Program,
+    mint_authority: Keypair,
+    generic_payer: Keypair,
+    simple_pool_context_factory: FactoryFixture,
+):
+    """
+    Tests:
+        - The default packet timeout can be updated and queried
+        - The default packet timeout must be within bounds
+        - Sent packets use the default packet timeout
+    """
+    provider = swap_pool_program.provider
+
+    spc = await simple_pool_context_factory(
+        2,              # user_count
+        1,              # asset_count
+        [10000],        # pool_assets_balances
+        [1],            # pool_assets_weights
+        None,           # pool_amplification
+        True            # connect_pool_with_itself
+    )
+
+    swap_asset             = spc.swap_pool_assets[0]
+    swapper                = spc.users[1]
+    swapper_asset_wallet   = swapper.token_accounts[0]
+    swap_pool_asset_wallet = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets[0]
+    swap_interface_state   = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+
+    assert spc.create_connection_result is not None
+
+    # Update and read the default timeout
+    new_timeout_blocks = 20000
+    await set_default_timeout(swap_interface_program, swap_interface_state, new_timeout_blocks, swap_interface_setup_master_keypair)
+    assert await query_default_timeout(swap_interface_program, swap_interface_state) == new_timeout_blocks
+
+    # Out of bounds timeouts
+    for invalid_timeout_blocks in [0, 2**64-1]:
+        with pytest.raises(Exception):
+            await set_default_timeout(swap_interface_program, swap_interface_state, invalid_timeout_blocks, swap_interface_setup_master_keypair)
+
+    # Only the configurator can update the default timeout
+    with pytest.raises(Exception):
+        await set_default_timeout(swap_interface_program, swap_interface_state, new_timeout_blocks, Keypair())
+
+    # Send a packet
+    await mint_to(provider, swapper_asset_wallet, swap_asset, mint_authority, 100)
+
+    slot_before_swap = (await provider.connection.get_slot()).value
+
+    cross_chain_swap_result = await perform_cross_chain_swap(
+        100,
+        swap_asset,
+        swapper_asset_wallet,
+        swapper.user_keypair,
+        SOLANA_CHAIN_ID,
+        swap_interface_state,
+        0,
+        swapper_asset_wallet,
+        0,
+        generic_payer,
+        swap_pool_program,
+        spc.swap_pool_state,
+        swap_pool_asset_wallet,
+        spc.swap_pool_authority,
+        swap_interface_program,
+        swap_interface_state,
+        spc.create_connection_result.connection_state,
+        polymerase_sender_program,
+        polymerase_emulator_setup_master_keypair
+    )
+
+    slot_after_swap = (await provider.connection.get_slot()).value
+
+    data = await polymerase_sender_program.account["IbcData"].fetch(cross_chain_swap_result.ibc_data)
+    assert slot_before_swap + new_timeout_blocks <= data.timeout_block_height <= slot_after_swap + new_timeout_blocks
+
+
+
+async def test_min_swap_amount(
+    swap_pool_program: Program,
+    mint_authority: Keypair,
+    simple_pool_context_factory: FactoryFixture,
+):
+    """
+    Tests:
+        - Only the dao authority can set the minimum swap amount of an asset
+        - The minimum swap amount can be queried
+        - Local swaps below the minimum swap amount fail
+        - Local swaps at the minimum swap amount succeed
+    """
+    provider = swap_pool_program.provider
+
+    spc = await simple_pool_context_factory(
+        2,                  # user_count
+        2,                  # asset_count
+        [10000, 10000],     # pool_assets_balances
+        [1, 1],             # pool_assets_weights
+        None,               # pool_amplification
+        False               # connect_pool_with_itself
+    )
+
+    dao_authority_keypair   = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.dao_authority_keypair
+    swap_pool_asset_wallets = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+    input_asset             = spc.swap_pool_assets[0]
+    output_asset            = spc.swap_pool_assets[1]
+    swapper                 = spc.users[1]
+
+    min_swap_amount = 100
+
+    assert await query_min_swap_amount(swap_pool_program, spc.swap_pool_state, input_asset) == 0
+
+    with pytest.raises(Exception):
+        await set_min_swap_amount(swap_pool_program, spc.swap_pool_state, input_asset, min_swap_amount, Keypair())
+
+    await set_min_swap_amount(swap_pool_program, spc.swap_pool_state, input_asset, min_swap_amount, dao_authority_keypair)
+
+    assert await query_min_swap_amount(swap_pool_program, spc.swap_pool_state, input_asset) == min_swap_amount
+    assert await query_min_swap_amount(swap_pool_program, spc.swap_pool_state, output_asset) == 0
+
+    await mint_to(provider, swapper.token_accounts[0], input_asset, mint_authority, 2*min_swap_amount)
+
+    async def local_swap(amount: int):
+        await perform_local_swap(
+            amount,
+            0,      # min yield
+            False,  # approx
+            input_asset,
+            swapper.token_accounts[0],
+            swapper.user_keypair,
+            output_asset,
+            swapper.token_accounts[1],
+            swap_pool_program,
+            spc.swap_pool_state,
+            swap_pool_asset_wallets[0],
+            swap_pool_asset_wallets[1],
+            spc.swap_pool_authority
+        )
+
+    # Below the minimum
+    with pytest.raises(Exception):
+        await local_swap(min_swap_amount - 1)
+
+    # At the minimum
+    await local_swap(min_swap_amount)
+
+
+
+async def test_swap_deadline(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    polymerase_emulator_setup_master_keypair: Keypair,
+    polymerase_sender_program: Program,
+    mint_authority: Keypair,
+    generic_payer: Keypair,
+    simple_pool_context_factory: FactoryFixture,
+):
+    """
+    Tests:
+        - Local swaps with an expired deadline fail
+        - Local swaps with a future deadline succeed
+        - Cross chain swaps with an expired deadline fail
+    """
+    provider = swap_pool_program.provider
+
+    spc = await simple_pool_context_factory(
+        2,                  # user_count
+        2,                  # asset_count
+        [10000, 10000],     # pool_assets_balances
+        [1, 1],             # pool_assets_weights
+        None,               # pool_amplification
+        True                # connect_pool_with_itself
+    )
+
+    swap_pool_asset_wallets = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+    swap_interface_state    = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+    input_asset             = spc.swap_pool_assets[0]
+    output_asset            = spc.swap_pool_assets[1]
+    swapper                 = spc.users[1]
+
+    assert spc.create_connection_result is not None
+    connection_state = spc.create_connection_result.connection_state
+
+    swap_amount = 100
+
+    past_deadline   = 1
+    future_deadline = int((datetime.now() + timedelta(hours=1)).timestamp())
+
+    await mint_to(provider, swapper.token_accounts[0], input_asset, mint_authority, 3*swap_amount)
+
+    async def local_swap(deadline: int | None):
+        await perform_local_swap(
+            swap_amount,
+            0,      # min yield
+            False,  # approx
+            input_asset,
+            swapper.token_accounts[0],
+            swapper.user_keypair,
+            output_asset,
+            swapper.token_accounts[1],
+            swap_pool_program,
+            spc.swap_pool_state,
+            swap_pool_asset_wallets[0],
+            swap_pool_asset_wallets[1],
+            spc.swap_pool_authority,
+            deadline=deadline
+        )
+
+    async def cross_chain_swap(deadline: int | None):
+        await perform_cross_chain_swap(
+            swap_amount,
+            input_asset,
+            swapper.token_accounts[0],
+            swapper.user_keypair,
+            SOLANA_CHAIN_ID,
+            swap_interface_state,           # Target pool
+            1,                              # Output asset index
+            swapper.token_accounts[1],      # Destination
+            0,                              # Escrow nonce
+            generic_payer,
+            swap_pool_program,
+            spc.swap_pool_state,
+            swap_pool_asset_wallets[0],
+            spc.swap_pool_authority,
+            swap_interface_program,
+            swap_interface_state,
+            connection_state,
+            polymerase_sender_program,
+            polymerase_emulator_setup_master_keypair,
+            deadline=deadline
+        )
+
+    # Local swaps
+    with pytest.raises(Exception):
+        await local_swap(past_deadline)
+
+    await local_swap(future_deadline)
+    await local_swap(None)
+
+    # Cross chain swaps
+    with pytest.raises(Exception):
+        await cross_chain_swap(past_deadline)
+
+    await cross_chain_swap(future_deadline)
+
+
+
+async def test_cumulative_volume(
+    swap_pool_program: Program,
+    mint_authority: Keypair,
+    simple_pool_context_factory: FactoryFixture,
+):
+    """
+    Tests:
+        - The cumulative volume of a new pool is zero
+        - Local swaps accumulate the input and output volumes of the swapped assets
+    """
+    provider = swap_pool_program.provider
+
+    spc = await simple_pool_context_factory(
+        2,                  # user_count
+        2,                  # asset_count
+        [10000, 10000],     # pool_assets_balances
+        [1, 1],             # pool_assets_weights
+        None,               # pool_amplification
+        False               # connect_pool_with_itself
+    )
+
+    swap_pool_asset_wallets = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+    swapper                 = spc.users[1]
+
+    volume = await query_cumulative_volume(swap_pool_program, spc.swap_pool_state)
+    assert volume.volume_in  == [0] * POOL_MAX_ASSET_COUNT
+    assert volume.volume_out == [0] * POOL_MAX_ASSET_COUNT
+
+    # (from asset index, to asset index, amount)
+    swaps = [(0, 1, 1000), (0, 1, 500), (1, 0, 200)]
+
+    await mint_to(provider, swapper.token_accounts[0], spc.swap_pool_assets[0], mint_authority, 1500)
+
+    expected_volume_in  = [0] * POOL_MAX_ASSET_COUNT
+    expected_volume_out = [0] * POOL_MAX_ASSET_COUNT
+
+    for from_index, to_index, amount in swaps:
+
+        result = await perform_local_swap(
+            amount,
+            0,      # min yield
+            False,  # approx
+            spc.swap_pool_assets[from_index],
+            swapper.token_accounts[from_index],
+            swapper.user_keypair,
+            spc.swap_pool_assets[to_index],
+            swapper.token_accounts[to_index],
+            swap_pool_program,
+            spc.swap_pool_state,
+            swap_pool_asset_wallets[from_index],
+            swap_pool_asset_wallets[to_index],
+            spc.swap_pool_authority
+        )
+
+        expected_volume_in[from_index] += amount
+        expected_volume_out[to_index]  += result.local_swap_event.data.withdrawn_asset_amount
+
+        volume = await query_cumulative_volume(swap_pool_program, spc.swap_pool_state)
+        assert volume.volume_in  == expected_volume_in
+        assert volume.volume_out == expected_volume_out
+
+
+
+async def test_asset_limit(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    simple_pool_context_factory: FactoryFixture,
+):
+    """
+    Tests:
+        - Only the dao authority can set the asset limit
+        - The asset limit capacity can be queried, and it shrinks as incoming swaps consume it
+        - Incoming swaps exceeding the asset limit fail
+        - Draining the limit of an asset does not block incoming swaps of another asset
+    """
+    provider = swap_pool_program.provider
+
+    spc = await simple_pool_context_factory(
+        2,                  # user_count
+        2,                  # asset_count
+        [10000, 10000],     # pool_assets_balances
+        [1, 1],             # pool_assets_weights
+        None,               # pool_amplification
+        True                # connect_pool_with_itself
+    )
+
+    dao_authority_keypair   = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.dao_authority_keypair
+    swap_pool_asset_wallets = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+    swap_interface_state    = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+    port_registration       = spc.create_and_setup_swap_pool_result.register_polymerase_port_result.polymerase_port
+    swapper                 = spc.users[1]
+
+    assert spc.create_connection_result is not None
+    connection_state = spc.create_connection_result.connection_state
+
+    max_asset_outflow = 3000
+    units_x64         = 2**62      # Yields ~2200 of a 10000 balance, weight 1 asset
+
+    async def receive(asset_index: int) -> int:
+        authority = Keypair() #not used for now
+
+        payload = encode_asset_swap_payload(
+            source_pool        = swap_interface_state,
+            target_pool        = swap_interface_state,
+            destination        = swapper.token_accounts[asset_index],
+            units_x64          = units_x64,
+            target_asset_index = asset_index
+        )
+
+        balance_before = (await get_account_info(provider, spc.swap_pool_assets[asset_index], swapper.token_accounts[asset_index])).amount
+
+        tx = await swap_interface_program.rpc["on_recv_packet"](
+            payload,
+            ctx=Context(
+                accounts={
+                    "port_registration": port_registration,
+                    "polymerase_authority": authority.public_key,
+                    "interface_state_account": swap_interface_state,
+                    "swap_pool": spc.swap_pool_state,
+                    "swap_pool_program": swap_pool_program.program_id,
+                    "token_program": TOKEN_PROGRAM_ID,
+                    "connection_state_account": connection_state
+                },
+                remaining_accounts=[
+                    AccountMeta(spc.swap_pool_assets[asset_index],     is_signer=False, is_writable=False ), # output_asset_mint
+                    AccountMeta(swapper.token_accounts[asset_index],   is_signer=False, is_writable=True  ), # output_asset_wallet
+                    AccountMeta(swap_pool_asset_wallets[asset_index],  is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
+                    AccountMeta(spc.swap_pool_authority,               is_signer=False, is_writable=False ), # swap_pool_authority
+                ],
+                signers=[authority]
+            )
+        )
+        await confirm_transaction(provider, tx)
+
+        balance_after = (await get_account_info(provider, spc.swap_pool_assets[asset_index], swapper.token_accounts[asset_index])).amount
+
+        return balance_after - balance_before
+
+    # Set the limit of the first asset only
+    with pytest.raises(Exception):
+        await set_max_asset_outflow(swap_pool_program, spc.swap_pool_state, spc.swap_pool_assets[0], max_asset_outflow, Keypair())
+
+    await set_max_asset_outflow(swap_pool_program, spc.swap_pool_state, spc.swap_pool_assets[0], max_asset_outflow, dao_authority_keypair)
+
+    assert await query_asset_limit_capacity(swap_pool_program, spc.swap_pool_state, spc.swap_pool_assets[0]) == max_asset_outflow
+    assert await query_asset_limit_capacity(swap_pool_program, spc.swap_pool_state, spc.swap_pool_assets[1]) == 2**64 - 1
+
+    # Consume most of the first asset capacity
+    received = await receive(0)
+    assert 0 < received <= max_asset_outflow
+
+    capacity = await query_asset_limit_capacity(swap_pool_program, spc.swap_pool_state, spc.swap_pool_assets[0])
+    assert max_asset_outflow - received <= capacity < max_asset_outflow - received + 10     # Allow for some decay
+
+    # The same swap now exceeds the first asset limit
+    with pytest.raises(Exception):
+        await receive(0)
+
+    # The second asset is not affected
+    assert await receive(1) > capacity
+
+
+
+async def test_would_send_exceed_limit(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    polymerase_emulator_setup_master_keypair: Keypair,
+    polymerase_sender_program: Program,
+    mint_authority: Keypair,
+    generic_payer: Keypair,
+    simple_pool_context_factory: FactoryFixture,
+):
+    """
+    Tests:
+        - Sends never exceed a limit on the source side, as the security limits only apply to incoming swaps
+        - Outgoing swaps do not consume any source side capacity
+        - The query fails for assets not contained in the pool
+    """
+    provider = swap_pool_program.provider
+
+    spc = await simple_pool_context_factory(
+        2,                      # user_count
+        2,                      # asset_count
+        [1000, 1000],           # pool_assets_balances
+        [1, 1],                 # pool_assets_weights
+        None,                   # pool_amplification
+        True                    # connect_pool_with_itself
+    )
+
+    swapper      = spc.users[1]
+    input_asset  = spc.swap_pool_assets[0]
+    input_wallet = swapper.token_accounts[0]
+    swap_amount  = 10000        # Larger than the pool balance
+
+    swap_pool_asset_wallets = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+    swap_interface_state    = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+
+    assert spc.create_connection_result is not None
+    connection_state = spc.create_connection_result.connection_state
+
+    response = await query_would_send_exceed_limit(swap_pool_program, spc.swap_pool_state, input_asset, swap_amount)
+    assert not response.exceeds_limit
+    assert response.remaining_capacity == 2**64 - 1
+
+    await mint_to(provider, input_wallet, input_asset, mint_authority, swap_amount)
+
+    await perform_cross_chain_swap(
+        swap_amount,
+        input_asset,
+        input_wallet,
+        swapper.user_keypair,
+        SOLANA_CHAIN_ID,
+        swap_interface_state,           # Target pool
+        1,                              # Output asset index
+        swapper.token_accounts[1],      # Destination
+        0,                              # Escrow nonce
+        generic_payer,
+        swap_pool_program,
+        spc.swap_pool_state,
+        swap_pool_asset_wallets[0],
+        spc.swap_pool_authority,
+        swap_interface_program,
+        swap_interface_state,
+        connection_state,
+        polymerase_sender_program,
+        polymerase_emulator_setup_master_keypair
+    )
+
+    # The outgoing swap did not consume any capacity
+    response = await query_would_send_exceed_limit(swap_pool_program, spc.swap_pool_state, input_asset, swap_amount)
+    assert not response.exceeds_limit
+    assert response.remaining_capacity == 2**64 - 1
+
+    with pytest.raises(Exception):
+        await query_would_send_exceed_limit(swap_pool_program, spc.swap_pool_state, Keypair().public_key, swap_amount)
+
+
+
+async def test_max_receivable_units(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    simple_pool_context_factory: FactoryFixture,
+):
+    """
+    Tests:
+        - The max receivable units of a new pool match the units inflow limit
+        - The max receivable units shrink after incoming swaps consume the limit capacity
+        - The max receivable units are capped by the asset limit
+    """
+    provider = swap_pool_program.provider
+
+    spc = await simple_pool_context_factory(
+        2,                  # user_count
+        2,                  # asset_count
+        [10000, 10000],     # pool_assets_balances
+        [1, 1],             # pool_assets_weights
+        None,               # pool_amplification
+        True                # connect_pool_with_itself
+    )
+
+    dao_authority_keypair   = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.dao_authority_keypair
+    swap_pool_asset_wallets = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+    swap_interface_state    = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+    port_registration       = spc.create_and_setup_swap_pool_result.register_polymerase_port_result.polymerase_port
+    swapper                 = spc.users[1]
+
+    assert spc.create_connection_result is not None
+    connection_state = spc.create_connection_result.connection_state
+
+    units_x64 = 2**62
+
+    async def max_receivable_units(asset_index: int) -> int:
+        return await query_max_receivable_units(
+            swap_pool_program,
+            spc.swap_pool_state,
+            swap_pool_asset_wallets,
+            spc.swap_pool_assets[asset_index]
+        )
+
+    async def receive(asset_index: int):
+        authority = Keypair() #not used for now
+
+        payload = encode_asset_swap_payload(
+            source_pool        = swap_interface_state,
+            target_pool        = swap_interface_state,
+            destination        = swapper.token_accounts[asset_index],
+            units_x64          = units_x64,
+            target_asset_index = asset_index
+        )
+
+        tx = await swap_interface_program.rpc["on_recv_packet"](
+            payload,
+            ctx=Context(
+                accounts={
+                    "port_registration": port_registration,
+                    "polymerase_authority": authority.public_key,
+                    "interface_state_account": swap_interface_state,
+                    "swap_pool": spc.swap_pool_state,
+                    "swap_pool_program": swap_pool_program.program_id,
+                    "token_program": TOKEN_PROGRAM_ID,
+                    "connection_state_account": connection_state
+                },
+                remaining_accounts=[
+                    AccountMeta(spc.swap_pool_assets[asset_index],     is_signer=False, is_writable=False ), # output_asset_mint
+                    AccountMeta(swapper.token_accounts[asset_index],   is_signer=False, is_writable=True  ), # output_asset_wallet
+                    AccountMeta(swap_pool_asset_wallets[asset_index],  is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
+                    AccountMeta(spc.swap_pool_authority,               is_signer=False, is_writable=False ), # swap_pool_authority
+                ],
+                signers=[authority]
+            )
+        )
+        await confirm_transaction(provider, tx)
+
+    # The units inflow limit is given by the sum of the pool weights
+    max_units_inflow_x64 = 2 * 2**64
+
+    assert await max_receivable_units(0) == max_units_inflow_x64
+    assert await max_receivable_units(1) == max_units_inflow_x64
+
+    # Consume part of the units inflow capacity
+    await receive(0)
+
+    max_units = await max_receivable_units(0)
+    assert max_units_inflow_x64 - units_x64 <= max_units < max_units_inflow_x64
+
+    # The units inflow limit is shared by all assets (allow for some decay between the queries)
+    assert max_units <= await max_receivable_units(1) < max_units_inflow_x64
+
+    # An asset limit below the units inflow capacity caps the max receivable units of that asset only
+    await set_max_asset_outflow(swap_pool_program, spc.swap_pool_state, spc.swap_pool_assets[1], 100, dao_authority_keypair)
+
+    assert await max_receivable_units(1) < units_x64
+    assert await max_receivable_units(0) >= max_units
+
+
+
+async def test_guaranteed_receive_asset(
+    swap_pool_program: Program,
+    simple_pool_context_factory: FactoryFixture,
+):
+    """
+    Tests:
+        - The guaranteed output matches the calc_receive_asset one if the security limits do not bind
+        - The guaranteed output is reduced if the units exceed the units inflow limit
+        - The guaranteed output is reduced to the asset limit capacity if the asset limit binds
+    """
+
+    spc = await simple_pool_context_factory(
+        1,                  # user_count
+        2,                  # asset_count
+        [10000, 10000],     # pool_assets_balances
+        [1, 1],             # pool_assets_weights
+        None,               # pool_amplification
+        False               # connect_pool_with_itself
+    )
+
+    dao_authority_keypair   = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.dao_authority_keypair
+    swap_pool_asset_wallets = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+
+    async def calc_receive(asset_index: int, units_x64: int) -> int:
+        return await query_calc_receive_asset(swap_pool_program, spc.swap_pool_state, swap_pool_asset_wallets, spc.swap_pool_assets[asset_index], units_x64)
+
+    async def guaranteed_receive(asset_index: int, units_x64: int) -> int:
+        return await query_guaranteed_receive_asset(swap_pool_program, spc.swap_pool_state, swap_pool_asset_wallets, spc.swap_pool_assets[asset_index], units_x64)
+
+    # The units inflow limit is given by the sum of the pool weights
+    max_units_inflow_x64 = 2 * 2**64
+
+    # The limits do not bind
+    assert await guaranteed_receive(0, 2**62) == await calc_receive(0, 2**62)
+
+    # The units exceed the units inflow limit
+    assert await guaranteed_receive(0, 3 * 2**64) == await calc_receive(0, max_units_inflow_x64) < await calc_receive(0, 3 * 2**64)
+
+    # The asset limit binds (for the capped asset only)
+    await set_max_asset_outflow(swap_pool_program, spc.swap_pool_state, spc.swap_pool_assets[1], 100, dao_authority_keypair)
+
+    assert await calc_receive(1, 2**62) > 100
+    assert await guaranteed_receive(1, 2**62) <= 100
+    assert await guaranteed_receive(0, 2**62) == await calc_receive(0, 2**62)
+
+
+
+async def test_price_accumulator(
+    swap_pool_program: Program,
+    mint_authority: Keypair,
+    simple_pool_context_factory: FactoryFixture,
+):
+    """
+    Tests:
+        - The price accumulators start accumulating on the first balance change of each asset
+        - The query brings both accumulators up to the current time, weighted by the current prices
+        - The accumulators are advanced on every balance change (e.g. donations), not only on swaps
+        - The accumulator increments ratio gives the ratio of the time-averaged prices of the assets
+    """
+    provider = swap_pool_program.provider
+
+    spc = await simple_pool_context_factory(
+        2,                  # user_count
+        2,                  # asset_count
+        [10000, 10000],     # pool_assets_balances
+        [1, 1],             # pool_assets_weights
+        None,               # pool_amplification
+        False               # connect_pool_with_itself
+    )
+
+    swap_pool_asset_wallets = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+    swapper                 = spc.users[1]
+
+    await mint_to(provider, swapper.token_accounts[0], spc.swap_pool_assets[0], mint_authority, 3000)
+
+    async def local_swap(amount: int):
+        await perform_local_swap(
+            amount,
+            0,      # min yield
+            False,  # approx
+            spc.swap_pool_assets[0],
+            swapper.token_accounts[0],
+            swapper.user_keypair,
+            spc.swap_pool_assets[1],
+            swapper.token_accounts[1],
+            swap_pool_program,
+            spc.swap_pool_state,
+            swap_pool_asset_wallets[0],
+            swap_pool_asset_wallets[1],
+            spc.swap_pool_authority
+        )
+
+    async def price_accumulator():
+        return await query_price_accumulator(
+            swap_pool_program,
+            spc.swap_pool_state,
+            swap_pool_asset_wallets,
+            spc.swap_pool_assets[0],
+            spc.swap_pool_assets[1]
+        )
+
+    async def price_cumulatives_timestamps():
+        return (await swap_pool_program.account["SwapPoolState"].fetch(spc.swap_pool_state)).price_cumulatives_timestamps
+
+    accumulator = await price_accumulator()
+    assert accumulator.timestamp > 0
+    assert accumulator.from_price_cumulative_x64 == 0 and accumulator.to_price_cumulative_x64 == 0
+
+    # The first swap only starts the accumulators
+    await local_swap(1000)
+
+    timestamps_0 = await price_cumulatives_timestamps()
+    assert timestamps_0[0] > 0 and timestamps_0[0] == timestamps_0[1]
+
+    # The query accumulates the current prices up to the current time (both accumulators refer to the same timestamp)
+    await asyncio.sleep(2)
+
+    accumulator_0 = await price_accumulator()
+    assert accumulator_0.timestamp > timestamps_0[0]
+    assert accumulator_0.from_price_cumulative_x64 > 0
+    assert accumulator_0.to_price_cumulative_x64 > 0
+
+    # After the swap the pool holds more of the 'from' asset, hence it is cheaper (less units per token)
+    assert accumulator_0.from_price_cumulative_x64 < accumulator_0.to_price_cumulative_x64
+
+    # Donations change the pool balances, hence they must advance the accumulator of the donated asset
+    await donate_to_swap_pool(
+        swap_pool_program,
+        [1000, 0],
+        spc.swap_pool_assets,
+        swapper.token_accounts,
+        swapper.user_keypair,
+        spc.swap_pool_state
+    )
+
+    timestamps_1 = await price_cumulatives_timestamps()
+    assert timestamps_1[0] > timestamps_0[0]
+    assert timestamps_1[1] == timestamps_0[1]
+
+    await asyncio.sleep(2)
+    await local_swap(1000)
+    await asyncio.sleep(2)
+
+    accumulator_1 = await price_accumulator()
+    assert accumulator_1.timestamp > accumulator_0.timestamp
+
+    from_increment = accumulator_1.from_price_cumulative_x64 - accumulator_0.from_price_cumulative_x64
+    to_increment   = accumulator_1.to_price_cumulative_x64 - accumulator_0.to_price_cumulative_x64
+
+    # The 'from' asset keeps getting cheaper relative to the 'to' asset
+    assert from_increment / to_increment < accumulator_0.from_price_cumulative_x64 / accumulator_0.to_price_cumulative_x64
+
+
+
+async def test_recv_packet_target_asset_index(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    simple_pool_context_factory: FactoryFixture,
+):
+    """
+    Tests:
+        - Received packets with a target asset index within the pool assets are accepted
+        - Received packets with a target asset index out of the pool assets are rejected with the 'Validate' fail reason
+    """
+    provider = swap_pool_program.provider
+
+    spc = await simple_pool_context_factory(
+        2,                  # user_count
+        2,                  # asset_count
+        [10000, 10000],     # pool_assets_balances
+        [1, 1],             # pool_assets_weights
+        None,               # pool_amplification
+        True                # connect_pool_with_itself
+    )
+
+    swap_pool_asset_wallets = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+    swap_interface_state    = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+    port_registration       = spc.create_and_setup_swap_pool_result.register_polymerase_port_result.polymerase_port
+    swapper                 = spc.users[1]
+
+    assert spc.create_connection_result is not None
+    connection_state = spc.create_connection_result.connection_state
+
+    async def receive(target_asset_index: int):
+        authority = Keypair() #not used for now
+
+        # The accounts of the last pool asset are provided, as the out of range index has none
+        payload = encode_asset_swap_payload(
+            source_pool        = swap_interface_state,
+            target_pool        = swap_interface_state,
+            destination        = swapper.token_accounts[1],
+            units_x64          = 2**62,
+            target_asset_index = target_asset_index
+        )
+
+        tx = await swap_interface_program.rpc["on_recv_packet"](
+            payload,
+            ctx=Context(
+                accounts={
+                    "port_registration": port_registration,
+                    "polymerase_authority": authority.public_key,
+                    "interface_state_account": swap_interface_state,
+                    "swap_pool": spc.swap_pool_state,
+                    "swap_pool_program": swap_pool_program.program_id,
+                    "token_program": TOKEN_PROGRAM_ID,
+                    "connection_state_account": connection_state
+                },
+                remaining_accounts=[
+                    AccountMeta(spc.swap_pool_assets[1],     is_signer=False, is_writable=False ), # output_asset_mint
+                    AccountMeta(swapper.token_accounts[1],   is_signer=False, is_writable=True  ), # output_asset_wallet
+                    AccountMeta(swap_pool_asset_wallets[1],  is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
+                    AccountMeta(spc.swap_pool_authority,     is_signer=False, is_writable=False ), # swap_pool_authority
+                ],
+                signers=[authority]
+            )
+        )
+        await confirm_transaction(provider, tx)
+
+    # In range
+    await receive(1)
+
+    # Out of range
+    async with TxEventListener() as ev_listener:
+        with pytest.raises(Exception):
+            await receive(2)
+
+        events = [event for event in await ev_listener.get_events(swap_interface_program) if event.name == "RecvPacketFailEvent"]
+
+    assert len(events) == 1
+    assert events[0].data.failReason.__class__.__name__ == "Validate"
+
+
+
+async def test_min_out_reference(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    simple_pool_context_factory: FactoryFixture,
+):
+    """
+    Tests:
+        - The asset swaps minimum output is reported in target asset units, and liquidity swaps have none
+        - Received packets with a minimum output up to the reported maximum are decoded
+        - Received packets with a minimum output above the reported maximum are rejected with the 'Decode' fail reason
+    """
+    provider = swap_pool_program.provider
+
+    spc = await simple_pool_context_factory(
+        2,                  # user_count
+        2,                  # asset_count
+        [10000, 10000],     # pool_assets_balances
+        [1, 1],             # pool_assets_weights
+        None,               # pool_amplification
+        True                # connect_pool_with_itself
+    )
+
+    reference = await query_min_out_reference(swap_pool_program, spc.swap_pool_state)
+    assert reference.asset_swap_min_out_basis == "to_asset"
+    assert reference.asset_swap_max_min_out == 2**64 - 1
+    assert reference.liquidity_swap_min_out_basis == "none"
+
+    swap_pool_asset_wallets = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+    swap_interface_state    = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+    port_registration       = spc.create_and_setup_swap_pool_result.register_polymerase_port_result.polymerase_port
+    swapper                 = spc.users[1]
+
+    assert spc.create_connection_result is not None
+    connection_state = spc.create_connection_result.connection_state
+
+    async def receive(min_output: int):
+        authority = Keypair() #not used for now
+
+        payload = encode_asset_swap_payload(
+            source_pool        = swap_interface_state,
+            target_pool        = swap_interface_state,
+            destination        = swapper.token_accounts[1],
+            units_x64          = 2**62,
+            target_asset_index = 1,
+            min_output         = min_output
+        )
+
+        tx = await swap_interface_program.rpc["on_recv_packet"](
+            payload,
+            ctx=Context(
+                accounts={
+                    "port_registration": port_registration,
+                    "polymerase_authority": authority.public_key,
+                    "interface_state_account": swap_interface_state,
+                    "swap_pool": spc.swap_pool_state,
+                    "swap_pool_program": swap_pool_program.program_id,
+                    "token_program": TOKEN_PROGRAM_ID,
+                    "connection_state_account": connection_state
+                },
+                remaining_accounts=[
+                    AccountMeta(spc.swap_pool_assets[1],     is_signer=False, is_writable=False ), # output_asset_mint
+                    AccountMeta(swapper.token_accounts[1],   is_signer=False, is_writable=True  ), # output_asset_wallet
+                    AccountMeta(swap_pool_asset_wallets[1],  is_signer=False, is_writable=True  ), # swap_pool_output_asset_wallet
+                    AccountMeta(spc.swap_pool_authority,     is_signer=False, is_writable=False ), # swap_pool_authority
+                ],
+                signers=[authority]
+            )
+        )
+        await confirm_transaction(provider, tx)
+
+    # Within the reported maximum
+    await receive(1)
+
+    # Above the reported maximum
+    async with TxEventListener() as ev_listener:
+        with pytest.raises(Exception):
+            await receive(reference.asset_swap_max_min_out + 1)
+
+        events = [event for event in await ev_listener.get_events(swap_interface_program) if event.name == "RecvPacketFailEvent"]
+
+    assert len(events) == 1
+    assert events[0].data.failReason.__class__.__name__ == "Decode"
+
+
+
+async def test_calc_local_swap_with_fee(
+    swap_pool_program: Program,
+    mint_authority: Keypair,
+    simple_pool_context_factory: FactoryFixture,
+):
+    """
+    Tests:
+        - The zero fee quote matches the output of the local swap
+        - A fee reduces the quoted output as if the input amount was reduced by the fee
+        - The fee is rounded up
+        - Fees above 100% are rejected
+    """
+    provider = swap_pool_program.provider
+
+    spc = await simple_pool_context_factory(
+        2,                  # user_count
+        2,                  # asset_count
+        [10000, 20000],     # pool_assets_balances
+        [1, 2],             # pool_assets_weights
+        None,               # pool_amplification
+        False               # connect_pool_with_itself
+    )
+
+    swap_pool_asset_wallets = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+    swapper                 = spc.users[1]
+
+    amount = 1000
+
+    async def quote(amount: int, vault_fee_bps: int) -> int:
+        return await query_calc_local_swap_with_fee(
+            swap_pool_program,
+            spc.swap_pool_state,
+            swap_pool_asset_wallets,
+            spc.swap_pool_assets[0],
+            spc.swap_pool_assets[1],
+            amount,
+            vault_fee_bps
+        )
+
+    zero_fee_quote = await quote(amount, 0)
+    high_fee_quote = await quote(amount, 5000)
+
+    assert high_fee_quote < zero_fee_quote
+    assert high_fee_quote == await quote(amount // 2, 0)
+
+    # Fractional fees are rounded up (a 1 bps fee on 10001 is 1.0001, charged as 2)
+    assert await quote(10001, 1) == await quote(9999, 0)
+
+    with pytest.raises(Exception):
+        await quote(amount, 10001)
+
+    # The quote is read-only and matches the actual swap (which is fee-free)
+    await mint_to(provider, swapper.token_accounts[0], spc.swap_pool_assets[0], mint_authority, amount)
+
+    result = await perform_local_swap(
+        amount,
+        0,      # min yield
+        False,  # approx
+        spc.swap_pool_assets[0],
+        swapper.token_accounts[0],
+        swapper.user_keypair,
+        spc.swap_pool_assets[1],
+        swapper.token_accounts[1],
+        swap_pool_program,
+        spc.swap_pool_state,
+        swap_pool_asset_wallets[0],
+        swap_pool_asset_wallets[1],
+        spc.swap_pool_authority
+    )
+
+    assert result.local_swap_event.data.withdrawnAssetAmount == zero_fee_quote
+
+
+
+async def test_price_impact(
+    swap_pool_program: Program,
+    simple_pool_context_factory: FactoryFixture,
+):
+    """
+    Tests:
+        - Small swaps have a negligible price impact
+        - The price impact grows with the swap size, approaching 100% near the depletion of the output asset
+    """
+
+    spc = await simple_pool_context_factory(
+        1,                          # user_count
+        2,                          # asset_count
+        [10**9, 2*10**9],           # pool_assets_balances
+        [1, 1],                     # pool_assets_weights
+        None,                       # pool_amplification
+        False                       # connect_pool_with_itself
+    )
+
+    swap_pool_asset_wallets = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+
+    async def price_impact(amount: int) -> int:
+        return await query_price_impact(
+            swap_pool_program,
+            spc.swap_pool_state,
+            swap_pool_asset_wallets,
+            spc.swap_pool_assets[0],
+            spc.swap_pool_assets[1],
+            amount
+        )
+
+    small_swap_impact = await price_impact(10**4)
+    large_swap_impact = await price_impact(10**9)       # Doubles the input asset balance
+    huge_swap_impact  = await price_impact(10**12)      # Depletes the output asset
+
+    assert small_swap_impact <= 1
+    assert large_swap_impact == 5000
+    assert 9900 < huge_swap_impact <= 10000
+
+
+
+async def test_depth_to_impact(
+    swap_pool_program: Program,
+    simple_pool_context_factory: FactoryFixture,
+):
+    """
+    Tests:
+        - The depth grows with the accepted price impact
+        - The price impact of a swap of the returned depth does not exceed the accepted one
+        - The price impact of a swap of one unit more than the returned depth exceeds the accepted one
+    """
+
+    spc = await simple_pool_context_factory(
+        1,                          # user_count
+        2,                          # asset_count
+        [10**9, 2*10**9],           # pool_assets_balances
+        [1, 1],                     # pool_assets_weights
+        None,                       # pool_amplification
+        False                       # connect_pool_with_itself
+    )
+
+    swap_pool_asset_wallets = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+
+    depths = []
+    for max_impact_bps in [10, 100, 1000, 5000, 9000]:
+        depth = await query_depth_to_impact(
+            swap_pool_program,
+            spc.swap_pool_state,
+            swap_pool_asset_wallets,
+            spc.swap_pool_assets[0],
+            spc.swap_pool_assets[1],
+            max_impact_bps
+        )
+
+        for amount in [depth, depth + 1]:
+            impact = await query_price_impact(
+                swap_pool_program,
+                spc.swap_pool_state,
+                swap_pool_asset_wallets,
+                spc.swap_pool_assets[0],
+                spc.swap_pool_assets[1],
+                amount
+            )
+            assert (impact <= max_impact_bps) == (amount == depth)
+
+        depths.append(depth)
+
+    assert depths == sorted(depths)
+    assert len(set(depths)) == len(depths)
+
+    # For equal weights, the price impact is x/(At + x), i.e. 50% when swapping At
+    assert abs(depths[3] - 10**9) <= 10**6
+
+
+
+async def test_depth_to_impact_amplified(
+    swap_pool_amplified_program: Program,
+    swap_pool_setup_master_keypair: Keypair,
+    provider: Provider,
+    mint_authority: Keypair,
+    generic_payer: Keypair
+):
+    """
+    Tests:
+        - The price impact of a swap of the returned depth does not exceed the accepted one on an amplified pool
+        - The price impact of a swap of one unit more than the returned depth exceeds the accepted one on an amplified pool
+    """
+
+    swap_pool_state_keypair = Keypair()
+
+    swap_pool_authority = get_swap_pool_authority(
+        swap_pool_amplified_program.program_id,
+        swap_pool_state_keypair.public_key,
+    )[0]
+
+    assets          = await create_mints(provider, mint_authority, 2)
+    assets_balances = [10**9, 2*10**9]
+    depositor       = (await create_users(provider, assets, generic_payer, 1))[0]
+
+    await fund_accounts(
+        provider                 = provider,
+        mints                    = assets,
+        mints_authority          = mint_authority,
+        token_accounts           = depositor.token_accounts,
+        balance                  = assets_balances,
+        delegate_authority       = swap_pool_authority,
+        delegate_balance         = assets_balances,
+        token_accounts_authority = depositor.user_keypair
+    )
+
+    initialize_swap_pool_state_result = await initialize_swap_pool_state(
+        swap_pool_program                       = swap_pool_amplified_program,
+        swap_pool_setup_master_keypair          = swap_pool_setup_master_keypair,
+        amplification                           = 2,
+        assets_weights                          = [1, 1],
+        assets_balances                         = assets_balances,
+        assets_mints                            = assets,
+        deposit_wallets                         = depositor.token_accounts,
+        depositor_pool_token_wallet_authority   = depositor.user_keypair.public_key,
+        swap_pool_state_keypair                 = swap_pool_state_keypair
+    )
+
+    swap_pool_state         = initialize_swap_pool_state_result.swap_pool_state
+    swap_pool_asset_wallets = initialize_swap_pool_state_result.swap_pool_asset_wallets
+
+    for max_impact_bps in [10, 100, 1000]:
+        depth = await query_depth_to_impact(
+            swap_pool_amplified_program,
+            swap_pool_state,
+            swap_pool_asset_wallets,
+            assets[0],
+            assets[1],
+            max_impact_bps
+        )
+
+        for amount in [depth, depth + 1]:
+            impact = await query_price_impact(
+                swap_pool_amplified_program,
+                swap_pool_state,
+                swap_pool_asset_wallets,
+                assets[0],
+                assets[1],
+                amount
+            )
+            assert (impact <= max_impact_bps) == (amount == depth)
+
+
+
+async def test_calc_receive_asset_large_units(
+    swap_pool_program: Program,
+    simple_pool_context_factory: FactoryFixture,
+):
+    """
+    Tests:
+        - Units that can be honored by the pool yield less than the pool balance
+        - Units too large for the pool (up to U256::MAX) are rejected instead of overflowing
+    """
+
+    pool_assets_balances = [10000, 20000]
+
+    spc = await simple_pool_context_factory(
+        1,                      # user_count
+        2,                      # asset_count
+        pool_assets_balances,
+        [1, 1],                 # pool_assets_weights
+        None,                   # pool_amplification
+        False                   # connect_pool_with_itself
+    )
+
+    swap_pool_asset_wallets = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+
+    async def calc_receive_asset(units_x64: int, approx: bool) -> int:
+        return await query_calc_receive_asset(
+            swap_pool_program,
+            spc.swap_pool_state,
+            swap_pool_asset_wallets,
+            spc.swap_pool_assets[1],
+            units_x64,
+            approx
+        )
+
+    for approx in [False, True]:
+        output = await calc_receive_asset(2**64, approx)
+        assert 0 < output < pool_assets_balances[1]
+
+        output = await calc_receive_asset(40 * 2**64, approx)
+        assert output <= pool_assets_balances[1]
+
+        for units_x64 in [2**256 - 1, 2**255]:
+            with pytest.raises(Exception):
+                await calc_receive_asset(units_x64, approx)
+
+    # The exact formula supports units up to 41 times the asset weight
+    with pytest.raises(Exception):
+        await calc_receive_asset(41 * 2**64, False)
+
+
+
+async def test_marginal_receive_rate(
+    swap_pool_program: Program,
+    simple_pool_context_factory: FactoryFixture,
+):
+    """
+    Tests:
+        - The marginal receive rate is the limit of calc_receive_asset(u)/u as u shrinks
+        - calc_receive_asset(u)/u never exceeds the marginal receive rate
+    """
+
+    pool_assets_balances = [10**12, 10**12]
+
+    spc = await simple_pool_context_factory(
+        1,                      # user_count
+        2,                      # asset_count
+        pool_assets_balances,
+        [1, 1],                 # pool_assets_weights
+        None,                   # pool_amplification
+        False                   # connect_pool_with_itself
+    )
+
+    swap_pool_asset_wallets = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_asset_wallets
+    to_asset                = spc.swap_pool_assets[1]
+
+    marginal_rate = await query_marginal_receive_rate(swap_pool_program, spc.swap_pool_state, swap_pool_asset_wallets, to_asset) / 2**64
+
+    # Bt · ln(2) / WB
+    assert marginal_rate == pytest.approx(pool_assets_balances[1] * math.log(2), rel=1e-9)
+
+    for approx in [False, True]:
+        previous_error = None
+
+        for units_x64 in [2**64 // 10, 2**64 // 10**2, 2**64 // 10**3]:
+            output = await query_calc_receive_asset(
+                swap_pool_program,
+                spc.swap_pool_state,
+                swap_pool_asset_wallets,
+                to_asset,
+                units_x64,
+                approx
+            )
+
+            average_rate = output / (units_x64 / 2**64)
+            assert average_rate <= marginal_rate
+
+            error = (marginal_rate - average_rate) / marginal_rate
+            if previous_error is not None:
+                assert error < previous_error
+            previous_error = error
+
+        assert previous_error < 1e-3
@@ -1,9 +1,11 @@
+from datetime import datetime, timedelta
+import pytest
 from solana.keypair import Keypair
 from solana.transaction import AccountMeta
 
 from anchorpy import Context, Program
 from conftest import FactoryFixture
-from utils.swap_pool_utils import create_connection
+from utils.swap_pool_utils import create_connection, perform_out_liquidity_swap
 from utils.common_utils import SOLANA_CHAIN_ID
 from utils.transaction_utils import TxEventListener, confirm_transaction
 from utils.token_utils import approve, get_account_info
@@ -94,6 +96,7 @@ async def test_liquidity_swap_with_self(
             target_pool_id,
             liquidity_provider_pool_token_wallet,
             swap_amount,
+            None,                   # deadline
             ctx=Context(
                 accounts={
                     "swap_pool_state_account": swap_pool_state,
@@ -358,6 +361,7 @@ async def test_liquidity_swap(
             target_pool_id,
             liquidity_provider_pool_token_wallet_2,
             swap_amount,
+            None,                   # deadline
             ctx=Context(
                 accounts={
                     "swap_pool_state_account": swap_pool_state_1,
@@ -482,3 +486,72 @@ async def test_liquidity_swap(
     assert liq_provider_account_info_after_swap.amount > liq_provider_account_info_mid_swap.amount
 
     assert (liq_provider_account_info_after_swap.amount - liq_provider_account_info_mid_swap.amount)/swap_amount > 0.95 #TODO add better test
+
+
+
+async def test_liquidity_swap_deadline(
+    swap_pool_program: Program,
+    swap_interface_program: Program,
+    polymerase_emulator_setup_master_keypair: Keypair,
+    polymerase_sender_program: Program,
+    simple_pool_context_factory: FactoryFixture
+):
+    """
+    Tests:
+        - Liquidity swaps with an expired deadline fail
+        - Liquidity swaps with a future deadline succeed
+    """
+    provider = swap_pool_program.provider
+
+    spc = await simple_pool_context_factory(
+        1,                  # user_count
+        2,                  # asset_count
+        [10000, 10000],     # pool_assets_balances
+        [1, 1],             # pool_assets_weights
+        None,               # pool_amplification
+        True                # connect_pool_with_itself
+    )
+
+    swap_pool_token_mint                 = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.swap_pool_token_mint
+    liquidity_provider_pool_token_wallet = spc.create_and_setup_swap_pool_result.initialize_swap_pool_state_result.depositor_pool_token_wallet_keypair.public_key
+    swap_interface_state                 = spc.create_and_setup_swap_pool_result.initialize_swap_interface_result.swap_interface_state
+    liquidity_provider                   = spc.users[0]
+
+    assert spc.create_connection_result is not None
+    connection_state = spc.create_connection_result.connection_state
+
+    swap_amount = 100
+
+    past_deadline   = 1
+    future_deadline = int((datetime.now() + timedelta(hours=1)).timestamp())
+
+    async def liquidity_swap(deadline: int | None):
+        await perform_out_liquidity_swap(
+            swap_amount,
+            liquidity_provider_pool_token_wallet,
+            liquidity_provider.user_keypair,
+            SOLANA_CHAIN_ID,
+            swap_interface_state,                   # Target pool
+            liquidity_provider_pool_token_wallet,   # Destination
+            swap_pool_program,
+            spc.swap_pool_state,
+            swap_pool_token_mint,
+            spc.swap_pool_authority,
+            swap_interface_program,
+            swap_interface_state,
+            connection_state,
+            polymerase_sender_program,
+            polymerase_emulator_setup_master_keypair,
+            deadline=deadline
+        )
+
+    pool_tokens_before = (await get_account_info(provider, swap_pool_token_mint, liquidity_provider_pool_token_wallet)).amount
+
+    with pytest.raises(Exception):
+        await liquidity_swap(past_deadline)
+
+    assert (await get_account_info(provider, swap_pool_token_mint, liquidity_provider_pool_token_wallet)).amount == pool_tokens_before
+
+    await liquidity_swap(future_deadline)
+
+    assert (await get_account_info(provider, swap_pool_token_mint, liquidity_provider_pool_token_wallet)).amount == pool_tokens_before - swap_amount
@@ -8,6 +8,7 @@ POOL_TOKEN_MINT_SEED        : str = "poolMint"
 POOL_AUTHORITY              : str = "poolAuth"
 INTERFACE_SWAP_AUTHORITY    : str = "intSwapAuth"
 POOL_ESCROW_SEED            : str = "poolEscrow"
+POOL_LOCKED_TOKENS_SEED     : str = "poolLocked"
 
 
 @cache
@@ -54,6 +55,20 @@ def get_swap_pool_token_mint(
     )
 
 
+@cache
+def get_swap_pool_locked_token_wallet(
+    swap_pool_program_id: PublicKey,
+    swap_pool_state_pubkey: PublicKey
+) -> Tuple[PublicKey, int]:
+    return PublicKey.find_program_address(
+        [
+            swap_pool_state_pubkey.__bytes__(),
+            POOL_LOCKED_TOKENS_SEED.encode('utf-8')
+        ],
+        swap_pool_program_id
+    )
+
+
 @cache
 def get_swap_pool_escrow_wallet(
     swap_pool_program_id: PublicKey,
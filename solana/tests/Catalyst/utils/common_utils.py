@@ -1,7 +1,8 @@
 import asyncio
 from dataclasses import dataclass
 
-from anchorpy import Program, Provider
+from anchorpy import Context, Program, Provider
+from borsh_construct import CStruct, String
 
 from solana.keypair import Keypair
 from solana.publickey import PublicKey
@@ -10,12 +11,15 @@ from .account_utils import get_swap_pool_authority
 from .simulator_utils import create_and_verify_catalyst_simulator
 from .swap_pool_utils import CreateAndSetupSwapPoolResult, create_and_setup_swap_pool, CreateConnectionResult, create_connection
 from .token_utils import fund_accounts, create_mint, create_token_account
+from .transaction_utils import simulate_view
 
 from catalyst_simulator import CatalystSimulator  # type: ignore
 
 SOLANA_CHAIN_ID       = 99
 SOLANA_CHAIN_ID_BYTES = SOLANA_CHAIN_ID.to_bytes(8, 'little')
 
+VERSION_RESPONSE_LAYOUT = CStruct("contract" / String, "version" / String)
+
 async def create_mints(provider: Provider, mint_authority: Keypair, count: int) -> list[PublicKey]:
     return list(await asyncio.gather(*[
         create_mint(
@@ -173,4 +177,24 @@ async def setup_simple_pool_test_env(
         create_and_setup_swap_pool_result,
         create_connection_result,
         catalyst_simulator
-    )
\ No newline at end of file
+    )
+
+@dataclass
+class VersionResponse:
+    contract: str
+    version: str
+
+async def query_version(
+    program: Program,
+    state_account_name: str,
+    state_account: PublicKey
+) -> VersionResponse:
+    """
+        Query the name and version of any of the Catalyst programs (SwapPool, SwapPoolAmplified or IBCInterface).
+        The state account must be owned by the queried program ("swap_pool_state_account" for the pools, "interface_state_account" for the interface).
+    """
+    return_data = await simulate_view(program, "version", ctx=Context(accounts={state_account_name: state_account}))
+
+    response = VERSION_RESPONSE_LAYOUT.parse(return_data)
+
+    return VersionResponse(response.contract, response.version)
@@ -1,15 +1,32 @@
 
 from typing import Tuple
 from anchorpy import Context, Program
+from borsh_construct import Bool, CStruct, U8, U32, U64
 from dataclasses import dataclass
 from solana.keypair import Keypair
 from solana.publickey import PublicKey
 from solana.system_program import SYS_PROGRAM_ID
 from solana.rpc.commitment import Commitment
+from solana.rpc.types import MemcmpOpts
 from solders.signature import Signature
 
-from utils.transaction_utils import confirm_transaction, DEFAULT_TX_COMMITMENT
+from utils.transaction_utils import confirm_transaction, simulate_view, DEFAULT_TX_COMMITMENT
 from utils.account_utils import get_swap_pool_authority
+from utils.verify_utils import int_to_u256_array
+
+
+CTX_ASSET_SWAP                 = 0x00
+CTX_ASSET_APPROX_SWAP          = 0x01
+
+DEFAULT_LIST_CONNECTIONS_LIMIT = 10
+MAX_LIST_CONNECTIONS_LIMIT     = 30
+
+CONNECTION_STATE_RESPONSE_LAYOUT = CStruct(
+    "chain"                       / U64,
+    "pool"                        / U8[32],
+    "connected_interface_program" / U8[32],
+    "enabled"                     / Bool
+)
 
 
 # Result classes ****************************************************************************************************************
@@ -25,6 +42,20 @@ class RegisterSwapInterfacePolymerasePortResult:
     tx: Signature
     polymerase_port: PublicKey
 
+@dataclass
+class ConnectionInfo:
+    connection_state: PublicKey
+    chain: int
+    pool: PublicKey
+    enabled: bool
+
+@dataclass
+class ConnectionStateResponse:
+    chain: int
+    pool: PublicKey
+    connected_interface_program: PublicKey
+    enabled: bool
+
 
 
 # Utils *************************************************************************************************************************
@@ -104,6 +135,187 @@ async def register_swap_interface_polymerase_port(
     return RegisterSwapInterfacePolymerasePortResult(tx, port_registration_account)
 
 
+async def set_max_units_per_packet(
+    swap_interface_program: Program,
+    swap_interface_state: PublicKey,
+    max_units_per_packet_x64: int | None,
+    swap_interface_configurator_keypair: Keypair,
+    commitment: Commitment = DEFAULT_TX_COMMITMENT
+) -> Signature:
+
+    tx = await swap_interface_program.rpc["set_max_units_per_packet"](
+        None if max_units_per_packet_x64 is None else int_to_u256_array(max_units_per_packet_x64),
+        ctx=Context(
+            accounts={
+                "interface_state_account": swap_interface_state,
+                "configurator": swap_interface_configurator_keypair.public_key
+            },
+            signers=[swap_interface_configurator_keypair]
+        )
+    )
+    await confirm_transaction(swap_interface_program.provider, tx, commitment=commitment)
+
+    return tx
+
+
+async def set_default_timeout(
+    swap_interface_program: Program,
+    swap_interface_state: PublicKey,
+    timeout_blocks: int,
+    swap_interface_configurator_keypair: Keypair,
+    commitment: Commitment = DEFAULT_TX_COMMITMENT
+) -> Signature:
+
+    tx = await swap_interface_program.rpc["set_default_timeout"](
+        timeout_blocks,
+        ctx=Context(
+            accounts={
+                "interface_state_account": swap_interface_state,
+                "configurator": swap_interface_configurator_keypair.public_key
+            },
+            signers=[swap_interface_configurator_keypair]
+        )
+    )
+    await confirm_transaction(swap_interface_program.provider, tx, commitment=commitment)
+
+    return tx
+
+
+async def set_pool_blocklisted(
+    swap_interface_program: Program,
+    swap_interface_state: PublicKey,
+    pool: PublicKey,
+    blocklisted: bool,
+    swap_interface_configurator_keypair: Keypair,
+    commitment: Commitment = DEFAULT_TX_COMMITMENT
+) -> Signature:
+
+    tx = await swap_interface_program.rpc["set_pool_blocklisted"](
+        pool,
+        blocklisted,
+        ctx=Context(
+            accounts={
+                "interface_state_account": swap_interface_state,
+                "configurator": swap_interface_configurator_keypair.public_key
+            },
+            signers=[swap_interface_configurator_keypair]
+        )
+    )
+    await confirm_transaction(swap_interface_program.provider, tx, commitment=commitment)
+
+    return tx
+
+
+async def query_is_blocklisted(
+    swap_interface_program: Program,
+    swap_interface_state: PublicKey,
+    pool: PublicKey
+) -> bool:
+
+    return_data = await simulate_view(
+        swap_interface_program,
+        "is_blocklisted",
+        pool,
+        ctx=Context(accounts={"interface_state_account": swap_interface_state})
+    )
+
+    return Bool.parse(return_data)
+
+
+async def query_default_timeout(
+    swap_interface_program: Program,
+    swap_interface_state: PublicKey
+) -> int:
+
+    return_data = await simulate_view(
+        swap_interface_program,
+        "default_timeout",
+        ctx=Context(accounts={"interface_state_account": swap_interface_state})
+    )
+
+    return U64.parse(return_data)
+
+
+async def query_send_asset_packet_size(
+    swap_interface_program: Program,
+    swap_interface_state: PublicKey
+) -> int:
+
+    return_data = await simulate_view(
+        swap_interface_program,
+        "estimate_send_asset_packet_size",
+        ctx=Context(accounts={"interface_state_account": swap_interface_state})
+    )
+
+    return U32.parse(return_data)
+
+
+async def query_connection_state(
+    swap_interface_program: Program,
+    swap_interface_state: PublicKey,
+    target_chain_id: int,
+    target_pool_id: PublicKey
+) -> ConnectionStateResponse:
+
+    connection_state = get_connection_state_account(
+        swap_interface_program.program_id,
+        swap_interface_state,
+        target_chain_id,
+        target_pool_id
+    )[0]
+
+    return_data = await simulate_view(
+        swap_interface_program,
+        "connection_state",
+        target_chain_id,
+        target_pool_id,
+        ctx=Context(
+            accounts={
+                "interface_state_account": swap_interface_state,
+                "connection_state_account": connection_state
+            }
+        )
+    )
+
+    response = CONNECTION_STATE_RESPONSE_LAYOUT.parse(return_data)
+
+    return ConnectionStateResponse(
+        chain                       = response.chain,
+        pool                        = PublicKey(bytes(response.pool)),
+        connected_interface_program = PublicKey(bytes(response.connected_interface_program)),
+        enabled                     = response.enabled
+    )
+
+
+def encode_asset_swap_payload(
+    source_pool: PublicKey,
+    target_pool: PublicKey,
+    destination: PublicKey,
+    units_x64: int,
+    target_asset_index: int,
+    min_output: int = 0,
+    source_swap_id: int = 0,
+    context: int = CTX_ASSET_SWAP
+) -> bytes:
+    """
+        Encode an asset swap payload following the layout used by the swap interface.
+    """
+
+    def u256_to_bytes(value: int) -> bytes:
+        return b"".join(limb.to_bytes(8, 'big') for limb in int_to_u256_array(value))
+
+    return (
+        context.to_bytes(1, 'big')
+        + source_pool.__bytes__()
+        + target_pool.__bytes__()
+        + destination.__bytes__()
+        + u256_to_bytes(units_x64)
+        + target_asset_index.to_bytes(1, 'big')
+        + u256_to_bytes(min_output)
+        + source_swap_id.to_bytes(4, 'big')
+    )
+
+
 def get_connection_state_account(
     swap_interface_program_id: PublicKey,
     swap_interface_state: PublicKey,
@@ -119,3 +331,41 @@ def get_connection_state_account(
         ],
         swap_interface_program_id
     )
+
+
+async def list_connections(
+    swap_interface_program: Program,
+    swap_interface_state: PublicKey,
+    start_after: Tuple[int, PublicKey] | None = None,
+    limit: int | None = None
+) -> list[ConnectionInfo]:
+    """
+        List the connections of a swap interface, ordered by (chain, pool). Connection state accounts store the
+        interface they belong to as their first field, which allows to filter them directly from the rpc.
+    """
+
+    connection_accounts = await swap_interface_program.account["ConnectionState"].all(
+        filters=[MemcmpOpts(offset=8, bytes=str(swap_interface_state))]     # Skip the account discriminator
+    )
+
+    connections = sorted(
+        [
+            ConnectionInfo(
+                connection_state = connection_account.public_key,
+                chain            = connection_account.account.chain,
+                pool             = connection_account.account.pool,
+                enabled          = connection_account.account.connected_interface_program != PublicKey(0)
+            ) for connection_account in connection_accounts
+        ],
+        key=lambda connection: (connection.chain, bytes(connection.pool))
+    )
+
+    if start_after is not None:
+        connections = [
+            connection for connection in connections
+            if (connection.chain, bytes(connection.pool)) > (start_after[0], bytes(start_after[1]))
+        ]
+
+    limit = min(limit or DEFAULT_LIST_CONNECTIONS_LIMIT, MAX_LIST_CONNECTIONS_LIMIT)
+
+    return connections[:limit]
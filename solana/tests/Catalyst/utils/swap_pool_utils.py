@@ -2,6 +2,7 @@
 from dataclasses import dataclass
 from typing import Any, List
 from anchorpy import Context, Program
+from borsh_construct import Bool, CStruct, String, U8, U32, U64, Vec
 from solana.keypair import Keypair
 from solana.publickey import PublicKey
 from solana.transaction import AccountMeta
@@ -11,14 +12,76 @@ from spl.token.constants import TOKEN_PROGRAM_ID
 from solana.sysvar import SYSVAR_RENT_PUBKEY
 from solders.signature import Signature
 
-from utils.account_utils import get_swap_pool_asset_wallet, get_swap_pool_authority, get_swap_pool_escrow_wallet, get_swap_pool_token_mint
+from utils.account_utils import get_swap_pool_asset_wallet, get_swap_pool_authority, get_swap_pool_escrow_wallet, get_swap_pool_locked_token_wallet, get_swap_pool_token_mint
 from utils.swap_interface_utils import InitializeSwapInterfaceResult, RegisterSwapInterfacePolymerasePortResult, get_connection_state_account, initialize_swap_interface_state, register_swap_interface_polymerase_port
 from utils.token_utils import approve, create_token_account, mint_to
-from utils.transaction_utils import TxEventListener, confirm_transaction, DEFAULT_TX_COMMITMENT
-from utils.verify_utils import int_to_u256_array, verify_local_swap_event, verify_out_swap_event
+from utils.transaction_utils import TxEventListener, confirm_transaction, simulate_view, DEFAULT_TX_COMMITMENT
+from utils.verify_utils import int_to_u256_array, u256_array_to_int, verify_local_swap_event, verify_out_swap_event
 
 
 POOL_MAX_ASSET_COUNT  = 3
+POOL_SETUP_WINDOW     = 60*60*24*7
+
+VAULT_TYPE_RESPONSE_LAYOUT           = CStruct("kind" / String, "schema_version" / U32)
+NORMALIZED_BALANCES_RESPONSE_LAYOUT  = CStruct("normalized_balances" / U64[4][POOL_MAX_ASSET_COUNT])
+CUMULATIVE_VOLUME_RESPONSE_LAYOUT    = CStruct(
+    "volume_in"  / U64[4][POOL_MAX_ASSET_COUNT],
+    "volume_out" / U64[4][POOL_MAX_ASSET_COUNT]
+)
+ASSETS_METADATA_RESPONSE_LAYOUT      = CStruct(
+    "pool_assets_mints" / U8[32][POOL_MAX_ASSET_COUNT],
+    "decimals"          / U8[POOL_MAX_ASSET_COUNT]
+)
+PRICE_ACCUMULATOR_RESPONSE_LAYOUT    = CStruct(
+    "from_price_cumulative_x64" / U64[4],
+    "to_price_cumulative_x64"   / U64[4],
+    "timestamp"                 / U64
+)
+CONFIG_RESPONSE_LAYOUT               = CStruct(
+    "kind"                / String,
+    "pool_assets_mints"   / U8[32][POOL_MAX_ASSET_COUNT],
+    "pool_assets_weights" / U64[POOL_MAX_ASSET_COUNT],
+    "amplification_x64"   / U64[4],
+    "min_swap_amounts"    / U64[POOL_MAX_ASSET_COUNT],
+    "ibc_interface"       / U8[32],
+    "dao_authority"       / U8[32],
+    "setup_master"        / U8[32]
+)
+CONNECTION_COMPATIBILITY_RESPONSE_LAYOUT = CStruct(
+    "compatible"           / Bool,
+    "local_asset_count"    / U8,
+    "expected_asset_count" / U8,
+    "mismatched_indices"   / Vec(U8)
+)
+MIN_INITIAL_LIQUIDITY_RESPONSE_LAYOUT = CStruct(
+    "initial_pool_tokens" / U64,
+    "locked_pool_tokens"  / U64
+)
+MIN_OUT_REFERENCE_RESPONSE_LAYOUT = CStruct(
+    "asset_swap_min_out_basis"     / String,
+    "asset_swap_max_min_out"       / U64,
+    "liquidity_swap_min_out_basis" / String
+)
+ESCROW_DETAILS_RESPONSE_LAYOUT = CStruct(
+    "asset_mint"        / U8[32],
+    "amount"            / U64,
+    "fallback_wallet"   / U8[32],
+    "target_chain"      / U64,
+    "target_pool"       / U8[32],
+    "to_asset_index"    / U8,
+    "target_withdrawer" / U8[32],
+    "units_x64"         / U64[4]
+)
+SIMULATE_SEND_ASSET_RESPONSE_LAYOUT = CStruct(
+    "units_x64"                / U64[4],
+    "asset_wallet_balance"     / U64,
+    "escrowed_asset_amount"    / U64,
+    "current_units_inflow_x64" / U64[4]
+)
+WOULD_SEND_EXCEED_LIMIT_RESPONSE_LAYOUT = CStruct(
+    "exceeds_limit"      / Bool,
+    "remaining_capacity" / U64
+)
 
 # Result classes ****************************************************************************************************************
 
@@ -46,6 +109,11 @@ class CreateConnectionResult:
     tx: Signature
     connection_state: PublicKey
 
+@dataclass
+class CreateConnectionsResult:
+    tx: Signature
+    connection_states: list[PublicKey]
+
 @dataclass
 class CreateAndSetupSwapPoolResult:
     initialize_swap_pool_state_result: InitializeSwapPoolStateResult
@@ -60,18 +128,105 @@ class AddLiquidityToSwapPoolResult:
     depositor_pool_token_wallet: PublicKey
 
 
+@dataclass
+class WithdrawFromSwapPoolResult:
+    tx: Signature
+    withdraw_event: Any
+
+
+@dataclass
+class DonateToSwapPoolResult:
+    tx: Signature
+    donate_event: Any
+
+
 @dataclass
 class PerformCrossChainSwapResult:
     tx: Signature
     ibc_data: PublicKey
     swap_escrow: PublicKey
     out_swap_event: Any
+    swap_escrow_created_event: Any
+    loopback_packet_event: Any | None
 
 @dataclass
 class PerformLocalSwapResult:
     tx: Signature
     local_swap_event: Any
 
+@dataclass
+class VaultTypeResponse:
+    kind: str
+    schema_version: int
+
+@dataclass
+class CumulativeVolumeResponse:
+    volume_in: list[int]
+    volume_out: list[int]
+
+@dataclass
+class AssetMetadata:
+    mint: PublicKey
+    decimals: int
+
+@dataclass
+class PriceAccumulatorResponse:
+    from_price_cumulative_x64: int
+    to_price_cumulative_x64: int
+    timestamp: int
+
+@dataclass
+class ConfigResponse:
+    kind: str
+    pool_assets_mints: list[PublicKey]
+    pool_assets_weights: list[int]
+    amplification_x64: int
+    min_swap_amounts: list[int]
+    ibc_interface: PublicKey
+    dao_authority: PublicKey
+    setup_master: PublicKey
+
+@dataclass
+class ConnectionCompatibilityResponse:
+    compatible: bool
+    local_asset_count: int
+    expected_asset_count: int
+    mismatched_indices: list[int]
+
+@dataclass
+class MinInitialLiquidityResponse:
+    initial_pool_tokens: int
+    locked_pool_tokens: int
+
+@dataclass
+class SimulateSendAssetResponse:
+    units_x64: int
+    asset_wallet_balance: int
+    escrowed_asset_amount: int
+    current_units_inflow_x64: int
+
+@dataclass
+class WouldSendExceedLimitResponse:
+    exceeds_limit: bool
+    remaining_capacity: int
+
+@dataclass
+class MinOutReferenceResponse:
+    asset_swap_min_out_basis: str
+    asset_swap_max_min_out: int
+    liquidity_swap_min_out_basis: str
+
+@dataclass
+class EscrowDetailsResponse:
+    asset_mint: PublicKey
+    amount: int
+    fallback_wallet: PublicKey
+    target_chain: int
+    target_pool: PublicKey
+    to_asset_index: int
+    target_withdrawer: PublicKey
+    units_x64: int
+
 # Utils *************************************************************************************************************************
 
 async def initialize_swap_pool_state(
@@ -159,6 +314,10 @@ async def initialize_swap_pool_state(
                 "depositor_pool_token_wallet_authority": depositor_pool_token_wallet_authority,
                 "depositor_pool_token_wallet": depositor_pool_token_wallet_keypair.public_key,
                 "swap_pool_authority": swap_pool_authority,
+                "swap_pool_locked_token_wallet": get_swap_pool_locked_token_wallet(
+                    swap_pool_program.program_id,
+                    swap_pool_state_keypair.public_key
+                )[0],
                 "rent": SYSVAR_RENT_PUBKEY,
                 "token_program": TOKEN_PROGRAM_ID,
                 "system_program": SYS_PROGRAM_ID
@@ -278,6 +437,62 @@ async def create_connection(
     return CreateConnectionResult(tx, connection_state)
 
 
+async def create_connections(
+    connections: list[tuple[int, PublicKey, PublicKey]],    # (target_chain_id, target_pool_id, target_program_id)
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    authority_keypair: Keypair, # Either setup_master or dao_authority
+    swap_interface_program: Program,
+    swap_interface_state: PublicKey,
+    rent_payer_keypair: Keypair,
+    commitment: Commitment = DEFAULT_TX_COMMITMENT
+) -> CreateConnectionsResult:
+
+    connection_states = [
+        get_connection_state_account(
+            swap_interface_program.program_id,
+            swap_interface_state,
+            target_chain_id,
+            target_pool_id
+        )[0] for target_chain_id, target_pool_id, _ in connections
+    ]
+
+    swap_pool_authority = get_swap_pool_authority(
+        swap_pool_program.program_id,
+        swap_pool_state
+    )[0]
+
+    connection_update_type = swap_pool_program.type["ConnectionUpdate"]
+
+    tx = await swap_pool_program.rpc["create_connections"](
+        [
+            connection_update_type(
+                chain=target_chain_id,
+                pool=target_pool_id,
+                interfaceProgram=target_program_id
+            ) for target_chain_id, target_pool_id, target_program_id in connections
+        ],
+        ctx=Context(
+            accounts={
+                "authority"                : authority_keypair.public_key,
+                "swap_pool_state_account"  : swap_pool_state,
+                "ibc_interface_program"    : swap_interface_program.program_id,
+                "swap_pool_authority"      : swap_pool_authority,
+                "rent_payer"               : rent_payer_keypair.public_key,
+                "interface_state_account"  : swap_interface_state,
+                "system_program"           : SYS_PROGRAM_ID
+            },
+            remaining_accounts=[
+                AccountMeta(connection_state, is_signer=False, is_writable=True) for connection_state in connection_states
+            ],
+            signers=[authority_keypair, rent_payer_keypair]
+        )
+    )
+    await confirm_transaction(swap_interface_program.provider, tx, commitment=commitment)
+
+    return CreateConnectionsResult(tx, connection_states)
+
+
 async def create_and_setup_swap_pool(
     swap_pool_program: Program,
     swap_pool_setup_master_keypair: Keypair,
@@ -411,6 +626,304 @@ async def add_liquidity_to_swap_pool(
 
 
 
+async def deposit_to_swap_pool(
+    swap_pool_program: Program,
+    pool_tokens_amount: int,
+    assets: list[PublicKey],
+    depositor_asset_wallets: list[PublicKey],
+    depositor_asset_wallets_authority_keypair: Keypair,
+    depositor_pool_token_wallet: PublicKey,
+    swap_pool_state: PublicKey,
+    swap_pool_token_mint: PublicKey,
+    swap_pool_authority: PublicKey,
+    commitment: Commitment = DEFAULT_TX_COMMITMENT
+) -> Signature:
+
+    provider = swap_pool_program.provider
+
+    # Give an allowance to the pool authority (the deposited amounts are derived by the pool from the pool tokens amount)
+    for depositor_asset_wallet in depositor_asset_wallets:
+        await approve(
+            provider,
+            depositor_asset_wallet,
+            depositor_asset_wallets_authority_keypair,
+            swap_pool_authority,
+            2**64 - 1,
+            commitment=commitment
+        )
+
+    # Group 'remaining accounts' (asset mint, swap pool asset wallet, depositor asset wallet)
+    remaining_accounts: list[AccountMeta] = []
+    for asset_index, asset in enumerate(assets):
+        remaining_accounts.append(AccountMeta(pubkey=asset, is_signer=False, is_writable=False))
+        remaining_accounts.append(AccountMeta(
+            pubkey      = get_swap_pool_asset_wallet(swap_pool_program.program_id, swap_pool_state, asset)[0],
+            is_signer   = False,
+            is_writable = True
+        ))
+        remaining_accounts.append(AccountMeta(pubkey=depositor_asset_wallets[asset_index], is_signer=False, is_writable=True))
+
+    tx = await swap_pool_program.rpc["deposit"](
+        pool_tokens_amount,
+        ctx=Context(
+            accounts={
+                "swap_pool_state_account": swap_pool_state,
+                "depositor_pool_token_wallet": depositor_pool_token_wallet,
+                "swap_pool_token_mint": swap_pool_token_mint,
+                "swap_pool_authority": swap_pool_authority,
+                "token_program": TOKEN_PROGRAM_ID
+            },
+            remaining_accounts=remaining_accounts
+        )
+    )
+    await confirm_transaction(provider, tx, commitment=commitment)
+
+    return tx
+
+
+
+async def withdraw_from_swap_pool(
+    swap_pool_program: Program,
+    pool_tokens_amount: int,
+    recipient: PublicKey | None,
+    assets: list[PublicKey],
+    destination_wallets: list[PublicKey],
+    withdrawer_pool_token_wallet: PublicKey,
+    withdrawer_pool_token_wallet_authority_keypair: Keypair,
+    swap_pool_state: PublicKey,
+    swap_pool_token_mint: PublicKey,
+    swap_pool_authority: PublicKey,
+    min_amounts: list[int] | None = None,      # If specified, a balanced withdrawal is performed (recipient must be None)
+    commitment: Commitment = DEFAULT_TX_COMMITMENT
+) -> WithdrawFromSwapPoolResult:
+
+    provider = swap_pool_program.provider
+
+    # Give pool token burn allowance to the pool authority
+    await approve(
+        provider,
+        withdrawer_pool_token_wallet,
+        withdrawer_pool_token_wallet_authority_keypair,
+        swap_pool_authority,
+        pool_tokens_amount,
+        commitment=commitment
+    )
+
+    # Group 'remaining accounts' (asset mint, swap pool asset wallet, destination asset wallet)
+    remaining_accounts: list[AccountMeta] = []
+    for asset_index, asset in enumerate(assets):
+        remaining_accounts.append(AccountMeta(pubkey=asset, is_signer=False, is_writable=False))
+        remaining_accounts.append(AccountMeta(
+            pubkey      = get_swap_pool_asset_wallet(swap_pool_program.program_id, swap_pool_state, asset)[0],
+            is_signer   = False,
+            is_writable = True
+        ))
+        remaining_accounts.append(AccountMeta(pubkey=destination_wallets[asset_index], is_signer=False, is_writable=True))
+
+    ctx = Context(
+        accounts={
+            "swap_pool_state_account": swap_pool_state,
+            "withdrawer_pool_token_wallet": withdrawer_pool_token_wallet,
+            "swap_pool_token_mint": swap_pool_token_mint,
+            "swap_pool_authority": swap_pool_authority,
+            "token_program": TOKEN_PROGRAM_ID
+        },
+        remaining_accounts=remaining_accounts
+    )
+
+    async with TxEventListener("WithdrawEvent") as ev_listener:
+        if min_amounts is None:
+            tx = await swap_pool_program.rpc["withdraw"](pool_tokens_amount, recipient, ctx=ctx)
+        else:
+            assert recipient is None
+            tx = await swap_pool_program.rpc["withdraw_balanced"](
+                pool_tokens_amount,
+                min_amounts + [0] * (POOL_MAX_ASSET_COUNT - len(min_amounts)),
+                ctx=ctx
+            )
+        await confirm_transaction(provider, tx, commitment=commitment)
+
+        withdraw_event = (await ev_listener.get_events(swap_pool_program))[0]
+
+    return WithdrawFromSwapPoolResult(tx, withdraw_event)
+
+
+
+async def donate_to_swap_pool(
+    swap_pool_program: Program,
+    amounts: list[int],
+    assets: list[PublicKey],
+    source_wallets: list[PublicKey],
+    source_wallets_authority_keypair: Keypair,
+    swap_pool_state: PublicKey,
+    commitment: Commitment = DEFAULT_TX_COMMITMENT
+) -> DonateToSwapPoolResult:
+
+    provider = swap_pool_program.provider
+
+    # Group 'remaining accounts' (asset mint, swap pool asset wallet, donor asset wallet)
+    # NOTE: No approval is required, as the donated assets are transferred with the authority of the donor (signer)
+    remaining_accounts: list[AccountMeta] = []
+    for asset_index, asset in enumerate(assets):
+
+        remaining_accounts.append(AccountMeta(pubkey=asset, is_signer=False, is_writable=False))
+        remaining_accounts.append(AccountMeta(
+            pubkey      = get_swap_pool_asset_wallet(swap_pool_program.program_id, swap_pool_state, asset)[0],
+            is_signer   = False,
+            is_writable = True
+        ))
+        remaining_accounts.append(AccountMeta(pubkey=source_wallets[asset_index], is_signer=False, is_writable=True))
+
+    async with TxEventListener("DonateEvent") as ev_listener:
+        tx = await swap_pool_program.rpc["donate"](
+            amounts + [0 for _ in range(POOL_MAX_ASSET_COUNT - len(amounts))],
+            ctx=Context(
+                accounts={
+                    "swap_pool_state_account": swap_pool_state,
+                    "donor": source_wallets_authority_keypair.public_key,
+                    "token_program": TOKEN_PROGRAM_ID
+                },
+                remaining_accounts=remaining_accounts,
+                signers=[source_wallets_authority_keypair]
+            )
+        )
+        await confirm_transaction(provider, tx, commitment=commitment)
+
+        donate_event = (await ev_listener.get_events(swap_pool_program))[0]
+
+    return DonateToSwapPoolResult(tx, donate_event)
+
+
+
+async def set_deposit_cap(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    asset: PublicKey,
+    deposit_cap: int,
+    dao_authority_keypair: Keypair,
+    commitment: Commitment = DEFAULT_TX_COMMITMENT
+) -> Signature:
+
+    tx = await swap_pool_program.rpc["set_deposit_cap"](
+        asset,
+        deposit_cap,
+        ctx=Context(
+            accounts={
+                "dao_authority": dao_authority_keypair.public_key,
+                "swap_pool_state_account": swap_pool_state
+            },
+            signers=[dao_authority_keypair]
+        )
+    )
+    await confirm_transaction(swap_pool_program.provider, tx, commitment=commitment)
+
+    return tx
+
+
+async def set_dao_authority(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    dao_authority_keypair: Keypair,
+    new_dao_authority_keypair: Keypair,
+    commitment: Commitment = DEFAULT_TX_COMMITMENT
+) -> Signature:
+
+    tx = await swap_pool_program.rpc["set_dao_authority"](
+        ctx=Context(
+            accounts={
+                "dao_authority": dao_authority_keypair.public_key,
+                "swap_pool_state_account": swap_pool_state,
+                "new_dao_authority": new_dao_authority_keypair.public_key
+            },
+            signers=[dao_authority_keypair, new_dao_authority_keypair]
+        )
+    )
+    await confirm_transaction(swap_pool_program.provider, tx, commitment=commitment)
+
+    return tx
+
+
+async def set_max_asset_outflow(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    asset: PublicKey,
+    max_asset_outflow: int,
+    dao_authority_keypair: Keypair,
+    commitment: Commitment = DEFAULT_TX_COMMITMENT
+) -> Signature:
+
+    tx = await swap_pool_program.rpc["set_max_asset_outflow"](
+        asset,
+        max_asset_outflow,
+        ctx=Context(
+            accounts={
+                "dao_authority": dao_authority_keypair.public_key,
+                "swap_pool_state_account": swap_pool_state
+            },
+            signers=[dao_authority_keypair]
+        )
+    )
+    await confirm_transaction(swap_pool_program.provider, tx, commitment=commitment)
+
+    return tx
+
+
+
+async def rescue_token(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    swap_pool_authority: PublicKey,
+    rescued_token_wallet: PublicKey,
+    recipient_wallet: PublicKey,
+    dao_authority_keypair: Keypair,
+    commitment: Commitment = DEFAULT_TX_COMMITMENT
+) -> Signature:
+
+    tx = await swap_pool_program.rpc["rescue_token"](
+        ctx=Context(
+            accounts={
+                "dao_authority": dao_authority_keypair.public_key,
+                "swap_pool_state_account": swap_pool_state,
+                "rescued_token_wallet": rescued_token_wallet,
+                "recipient_wallet": recipient_wallet,
+                "swap_pool_authority": swap_pool_authority,
+                "token_program": TOKEN_PROGRAM_ID
+            },
+            signers=[dao_authority_keypair]
+        )
+    )
+    await confirm_transaction(swap_pool_program.provider, tx, commitment=commitment)
+
+    return tx
+
+
+
+async def set_min_swap_amount(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    asset: PublicKey,
+    min_swap_amount: int,
+    dao_authority_keypair: Keypair,
+    commitment: Commitment = DEFAULT_TX_COMMITMENT
+) -> Signature:
+
+    tx = await swap_pool_program.rpc["set_min_swap_amount"](
+        asset,
+        min_swap_amount,
+        ctx=Context(
+            accounts={
+                "dao_authority": dao_authority_keypair.public_key,
+                "swap_pool_state_account": swap_pool_state
+            },
+            signers=[dao_authority_keypair]
+        )
+    )
+    await confirm_transaction(swap_pool_program.provider, tx, commitment=commitment)
+
+    return tx
+
+
+
 async def perform_local_swap(
     input_amount: int,
     minimum_output_amount: int,
@@ -425,6 +938,7 @@ async def perform_local_swap(
     swap_pool_input_asset_wallet: PublicKey,
     swap_pool_output_asset_wallet: PublicKey,
     swap_pool_authority: PublicKey,
+    deadline: int | None = None,
     commitment: Commitment = DEFAULT_TX_COMMITMENT
 ) -> PerformLocalSwapResult:
 
@@ -442,6 +956,7 @@ async def perform_local_swap(
             input_amount,
             minimum_output_amount,
             approx,
+            deadline,
             ctx=Context(
                 accounts={
                     "swap_pool_state_account": swap_pool_state,
@@ -499,6 +1014,7 @@ async def perform_cross_chain_swap(
     fallback_wallet: PublicKey | None = None,
     approx_from: bool = False,
     approx_to:bool = False,
+    deadline: int | None = None,
     commitment: Commitment = DEFAULT_TX_COMMITMENT
 ) -> PerformCrossChainSwapResult:
 
@@ -523,7 +1039,7 @@ async def perform_cross_chain_swap(
 
     polymerase_ibc_data_account_keypair = Keypair() 
 
-    async with TxEventListener("OutSwapEvent") as ev_listener:
+    async with TxEventListener() as ev_listener:
         tx = await swap_pool_program.rpc["out_swap"](
             target_chain_id,
             target_pool_id,
@@ -532,9 +1048,9 @@ async def perform_cross_chain_swap(
             input_amount,
             int_to_u256_array(min_output),
             swap_escrow_nonce,
-            fallback_wallet,
             approx_from,
             approx_to,
+            deadline,
             ctx=Context(
                 accounts={
                     "swap_pool_state_account": swap_pool_state,
@@ -542,6 +1058,7 @@ async def perform_cross_chain_swap(
                     "input_asset_wallet": source_wallet,
                     "swap_pool_input_asset_wallet": swap_pool_input_asset_wallet,
                     "token_program": TOKEN_PROGRAM_ID,
+                    "fallback_wallet": fallback_wallet,
                     "swap_escrow": swap_escrow_wallet,
                     "swap_escrow_rent_payer": swap_escrow_payer.public_key,
                     "ibc_interface_program": swap_interface_program.program_id,
@@ -563,7 +1080,11 @@ async def perform_cross_chain_swap(
         await confirm_transaction(swap_pool_program.provider, tx, commitment=commitment)
 
         # Verify the deposit event
-        out_swap_event = (await ev_listener.get_events(swap_pool_program))[0]
+        events = await ev_listener.get_events(swap_pool_program, swap_interface_program)
+
+        out_swap_event            = [event for event in events if event.name == "OutSwapEvent"][0]
+        swap_escrow_created_event = [event for event in events if event.name == "SwapEscrowCreatedEvent"][0]
+        loopback_packet_event     = next((event for event in events if event.name == "LoopbackPacketEvent"), None)
 
         verify_out_swap_event(
             out_swap_event          = out_swap_event,
@@ -582,6 +1103,740 @@ async def perform_cross_chain_swap(
         tx,
         polymerase_ibc_data_account_keypair.public_key,
         swap_escrow_wallet,
-        out_swap_event
+        out_swap_event,
+        swap_escrow_created_event,
+        loopback_packet_event
+    )
+
+
+
+async def perform_out_liquidity_swap(
+    pool_tokens_amount: int,
+    source_pool_token_wallet: PublicKey,
+    source_pool_token_wallet_authority_keypair: Keypair,
+    target_chain_id: int,
+    target_pool_id: PublicKey,
+    destination_pool_token_wallet: PublicKey,
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    swap_pool_token_mint: PublicKey,
+    swap_pool_authority: PublicKey,
+    swap_interface_program: Program,
+    swap_interface_state: PublicKey,
+    swap_interface_connection_state: PublicKey,
+    polymerase_sender_program: Program,
+    polymerase_payer: Keypair,
+    deadline: int | None = None,
+    commitment: Commitment = DEFAULT_TX_COMMITMENT
+) -> Signature:
+
+    await approve(
+        swap_pool_program.provider,
+        source_pool_token_wallet,
+        source_pool_token_wallet_authority_keypair,
+        swap_pool_authority,
+        pool_tokens_amount,
+        commitment=commitment
+    )
+
+    polymerase_ibc_data_account_keypair = Keypair()
+
+    tx = await swap_pool_program.rpc["out_liquidity_swap"](
+        target_chain_id,
+        target_pool_id,
+        destination_pool_token_wallet,
+        pool_tokens_amount,
+        deadline,
+        ctx=Context(
+            accounts={
+                "swap_pool_state_account": swap_pool_state,
+                "liquidity_provider_pool_token_wallet": source_pool_token_wallet,
+                "swap_pool_token_mint": swap_pool_token_mint,
+                "token_program": TOKEN_PROGRAM_ID,
+                "ibc_interface_program": swap_interface_program.program_id,
+                "swap_pool_authority": swap_pool_authority,
+                "interface_state_account": swap_interface_state,
+                "connection_state_account": swap_interface_connection_state,
+                "polymerase_sender_program": polymerase_sender_program.program_id,
+                "polymerase_ibc_data": polymerase_ibc_data_account_keypair.public_key,
+                "polymerase_ibc_data_account_payer": polymerase_payer.public_key,
+                "system_program": SYS_PROGRAM_ID
+            },
+            signers=[polymerase_payer, polymerase_ibc_data_account_keypair]
+        )
+    )
+    await confirm_transaction(swap_pool_program.provider, tx, commitment=commitment)
+
+    return tx
+
+
+
+async def query_capabilities(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey
+) -> list[str]:
+    """
+        Query the instructions exposed by the pool type on top of the ones common to all pool types.
+    """
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "capabilities",
+        ctx=Context(accounts={"swap_pool_state_account": swap_pool_state})
+    )
+
+    return list(Vec(String).parse(return_data))
+
+
+
+async def query_vault_type(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey
+) -> VaultTypeResponse:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "vault_type",
+        ctx=Context(accounts={"swap_pool_state_account": swap_pool_state})
+    )
+
+    response = VAULT_TYPE_RESPONSE_LAYOUT.parse(return_data)
+
+    return VaultTypeResponse(response.kind, response.schema_version)
+
+
+
+async def query_config(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey
+) -> ConfigResponse:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "config",
+        ctx=Context(accounts={"swap_pool_state_account": swap_pool_state})
+    )
+
+    response = CONFIG_RESPONSE_LAYOUT.parse(return_data)
+
+    return ConfigResponse(
+        kind                = response.kind,
+        pool_assets_mints   = [PublicKey(bytes(mint)) for mint in response.pool_assets_mints],
+        pool_assets_weights = list(response.pool_assets_weights),
+        amplification_x64   = u256_array_to_int(list(response.amplification_x64)),
+        min_swap_amounts    = list(response.min_swap_amounts),
+        ibc_interface       = PublicKey(bytes(response.ibc_interface)),
+        dao_authority       = PublicKey(bytes(response.dao_authority)),
+        setup_master        = PublicKey(bytes(response.setup_master))
+    )
+
+
+
+async def query_cumulative_volume(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey
+) -> CumulativeVolumeResponse:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "cumulative_volume",
+        ctx=Context(accounts={"swap_pool_state_account": swap_pool_state})
+    )
+
+    response = CUMULATIVE_VOLUME_RESPONSE_LAYOUT.parse(return_data)
+
+    return CumulativeVolumeResponse(
+        volume_in  = [u256_array_to_int(list(volume)) for volume in response.volume_in],
+        volume_out = [u256_array_to_int(list(volume)) for volume in response.volume_out]
+    )
+
+
+
+async def query_normalized_balances(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    swap_pool_asset_wallets: list[PublicKey]
+) -> list[int]:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "normalized_balances",
+        ctx=Context(
+            accounts={"swap_pool_state_account": swap_pool_state},
+            remaining_accounts=[
+                AccountMeta(wallet, is_signer=False, is_writable=False) for wallet in swap_pool_asset_wallets
+            ]
+        )
+    )
+
+    response = NORMALIZED_BALANCES_RESPONSE_LAYOUT.parse(return_data)
+
+    return [u256_array_to_int(list(balance)) for balance in response.normalized_balances[:len(swap_pool_asset_wallets)]]
+
+
+
+async def query_total_value_locked(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    swap_pool_asset_wallets: list[PublicKey],
+    reference_asset: PublicKey
+) -> int:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "total_value_locked",
+        reference_asset,
+        ctx=Context(
+            accounts={"swap_pool_state_account": swap_pool_state},
+            remaining_accounts=[
+                AccountMeta(wallet, is_signer=False, is_writable=False) for wallet in swap_pool_asset_wallets
+            ]
+        )
+    )
+
+    return U64.parse(return_data)
+
+
+
+async def query_swappable_assets(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    swap_pool_asset_wallets: list[PublicKey],
+    from_asset: PublicKey
+) -> list[PublicKey]:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "swappable_assets",
+        from_asset,
+        ctx=Context(
+            accounts={"swap_pool_state_account": swap_pool_state},
+            remaining_accounts=[
+                AccountMeta(wallet, is_signer=False, is_writable=False) for wallet in swap_pool_asset_wallets
+            ]
+        )
+    )
+
+    return [PublicKey(bytes(mint)) for mint in Vec(U8[32]).parse(return_data)]
+
+
+
+async def query_asset_limit_capacity(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    asset: PublicKey
+) -> int:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "asset_limit_capacity",
+        asset,
+        ctx=Context(accounts={"swap_pool_state_account": swap_pool_state})
+    )
+
+    return U64.parse(return_data)
+
+
+
+async def query_would_send_exceed_limit(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    from_asset: PublicKey,
+    amount: int
+) -> WouldSendExceedLimitResponse:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "would_send_exceed_limit",
+        from_asset,
+        amount,
+        ctx=Context(accounts={"swap_pool_state_account": swap_pool_state})
+    )
+
+    response = WOULD_SEND_EXCEED_LIMIT_RESPONSE_LAYOUT.parse(return_data)
+
+    return WouldSendExceedLimitResponse(
+        exceeds_limit      = response.exceeds_limit,
+        remaining_capacity = response.remaining_capacity
+    )
+
+
+
+async def query_price_accumulator(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    swap_pool_asset_wallets: list[PublicKey],
+    from_asset: PublicKey,
+    to_asset: PublicKey
+) -> PriceAccumulatorResponse:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "price_accumulator",
+        from_asset,
+        to_asset,
+        ctx=Context(
+            accounts={"swap_pool_state_account": swap_pool_state},
+            remaining_accounts=[
+                AccountMeta(wallet, is_signer=False, is_writable=False) for wallet in swap_pool_asset_wallets
+            ]
+        )
+    )
+
+    response = PRICE_ACCUMULATOR_RESPONSE_LAYOUT.parse(return_data)
+
+    return PriceAccumulatorResponse(
+        from_price_cumulative_x64 = u256_array_to_int(list(response.from_price_cumulative_x64)),
+        to_price_cumulative_x64   = u256_array_to_int(list(response.to_price_cumulative_x64)),
+        timestamp                 = response.timestamp
+    )
+
+
+
+async def query_min_initial_liquidity(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey
+) -> MinInitialLiquidityResponse:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "min_initial_liquidity",
+        ctx=Context(accounts={"swap_pool_state_account": swap_pool_state})
+    )
+
+    response = MIN_INITIAL_LIQUIDITY_RESPONSE_LAYOUT.parse(return_data)
+
+    return MinInitialLiquidityResponse(
+        initial_pool_tokens = response.initial_pool_tokens,
+        locked_pool_tokens  = response.locked_pool_tokens
+    )
+
+
+
+async def query_min_out_reference(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey
+) -> MinOutReferenceResponse:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "min_out_reference",
+        ctx=Context(accounts={"swap_pool_state_account": swap_pool_state})
+    )
+
+    response = MIN_OUT_REFERENCE_RESPONSE_LAYOUT.parse(return_data)
+
+    return MinOutReferenceResponse(
+        asset_swap_min_out_basis     = response.asset_swap_min_out_basis,
+        asset_swap_max_min_out       = response.asset_swap_max_min_out,
+        liquidity_swap_min_out_basis = response.liquidity_swap_min_out_basis
+    )
+
+
+
+async def query_escrow_counts(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey
+) -> int:
+    """
+        Returns the number of open asset swap escrows.
+    """
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "escrow_counts",
+        ctx=Context(accounts={"swap_pool_state_account": swap_pool_state})
+    )
+
+    return U64.parse(return_data)
+
+
+
+async def query_escrow_details(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    escrow_nonce: int
+) -> EscrowDetailsResponse:
+    """
+        Returns the parameters of the outgoing swap held by the escrow with the given nonce.
+    """
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "escrow_details",
+        escrow_nonce,
+        ctx=Context(
+            accounts={
+                "swap_pool_state_account": swap_pool_state,
+                "swap_escrow": get_swap_pool_escrow_wallet(swap_pool_program.program_id, swap_pool_state, escrow_nonce)[0]
+            }
+        )
+    )
+
+    response = ESCROW_DETAILS_RESPONSE_LAYOUT.parse(return_data)
+
+    return EscrowDetailsResponse(
+        asset_mint        = PublicKey(bytes(response.asset_mint)),
+        amount            = response.amount,
+        fallback_wallet   = PublicKey(bytes(response.fallback_wallet)),
+        target_chain      = response.target_chain,
+        target_pool       = PublicKey(bytes(response.target_pool)),
+        to_asset_index    = response.to_asset_index,
+        target_withdrawer = PublicKey(bytes(response.target_withdrawer)),
+        units_x64         = u256_array_to_int(list(response.units_x64))
+    )
+
+
+
+async def query_is_dao_authority(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    address: PublicKey
+) -> bool:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "is_dao_authority",
+        address,
+        ctx=Context(accounts={"swap_pool_state_account": swap_pool_state})
+    )
+
+    return Bool.parse(return_data)
+
+
+
+async def query_check_connection_compatibility(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    expected_assets: list[PublicKey]
+) -> ConnectionCompatibilityResponse:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "check_connection_compatibility",
+        expected_assets,
+        ctx=Context(accounts={"swap_pool_state_account": swap_pool_state})
+    )
+
+    response = CONNECTION_COMPATIBILITY_RESPONSE_LAYOUT.parse(return_data)
+
+    return ConnectionCompatibilityResponse(
+        compatible           = response.compatible,
+        local_asset_count    = response.local_asset_count,
+        expected_asset_count = response.expected_asset_count,
+        mismatched_indices   = list(response.mismatched_indices)
+    )
+
+
+
+async def query_assets_metadata(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    assets_mints: list[PublicKey]
+) -> list[AssetMetadata]:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "assets_metadata",
+        ctx=Context(
+            accounts={"swap_pool_state_account": swap_pool_state},
+            remaining_accounts=[
+                AccountMeta(mint, is_signer=False, is_writable=False) for mint in assets_mints
+            ]
+        )
+    )
+
+    response = ASSETS_METADATA_RESPONSE_LAYOUT.parse(return_data)
+
+    return [
+        AssetMetadata(PublicKey(bytes(mint)), decimals)
+        for mint, decimals in zip(response.pool_assets_mints[:len(assets_mints)], response.decimals)
+    ]
+
+
+
+async def query_max_receivable_units(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    swap_pool_asset_wallets: list[PublicKey],
+    to_asset: PublicKey
+) -> int:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "max_receivable_units",
+        to_asset,
+        ctx=Context(
+            accounts={"swap_pool_state_account": swap_pool_state},
+            remaining_accounts=[
+                AccountMeta(wallet, is_signer=False, is_writable=False) for wallet in swap_pool_asset_wallets
+            ]
+        )
+    )
+
+    return u256_array_to_int(list(U64[4].parse(return_data)))
+
+
+
+async def query_calc_receive_asset(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    swap_pool_asset_wallets: list[PublicKey],
+    to_asset: PublicKey,
+    units_x64: int,
+    approx: bool = False
+) -> int:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "calc_receive_asset",
+        to_asset,
+        int_to_u256_array(units_x64),
+        approx,
+        ctx=Context(
+            accounts={"swap_pool_state_account": swap_pool_state},
+            remaining_accounts=[
+                AccountMeta(wallet, is_signer=False, is_writable=False) for wallet in swap_pool_asset_wallets
+            ]
+        )
+    )
+
+    return U64.parse(return_data)
+
+
+
+async def query_marginal_receive_rate(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    swap_pool_asset_wallets: list[PublicKey],
+    to_asset: PublicKey
+) -> int:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "marginal_receive_rate",
+        to_asset,
+        ctx=Context(
+            accounts={"swap_pool_state_account": swap_pool_state},
+            remaining_accounts=[
+                AccountMeta(wallet, is_signer=False, is_writable=False) for wallet in swap_pool_asset_wallets
+            ]
+        )
+    )
+
+    return u256_array_to_int(list(U64[4].parse(return_data)))
+
+
+
+async def query_guaranteed_receive_asset(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    swap_pool_asset_wallets: list[PublicKey],
+    to_asset: PublicKey,
+    units_x64: int,
+    approx: bool = False
+) -> int:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "guaranteed_receive_asset",
+        to_asset,
+        int_to_u256_array(units_x64),
+        approx,
+        ctx=Context(
+            accounts={"swap_pool_state_account": swap_pool_state},
+            remaining_accounts=[
+                AccountMeta(wallet, is_signer=False, is_writable=False) for wallet in swap_pool_asset_wallets
+            ]
+        )
+    )
+
+    return U64.parse(return_data)
+
+
+
+async def query_simulate_send_asset(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    swap_pool_asset_wallets: list[PublicKey],
+    from_asset: PublicKey,
+    amount: int,
+    approx: bool = False
+) -> SimulateSendAssetResponse:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "simulate_send_asset",
+        from_asset,
+        amount,
+        approx,
+        ctx=Context(
+            accounts={"swap_pool_state_account": swap_pool_state},
+            remaining_accounts=[
+                AccountMeta(wallet, is_signer=False, is_writable=False) for wallet in swap_pool_asset_wallets
+            ]
+        )
+    )
+
+    response = SIMULATE_SEND_ASSET_RESPONSE_LAYOUT.parse(return_data)
+
+    return SimulateSendAssetResponse(
+        units_x64                = u256_array_to_int(list(response.units_x64)),
+        asset_wallet_balance     = response.asset_wallet_balance,
+        escrowed_asset_amount    = response.escrowed_asset_amount,
+        current_units_inflow_x64 = u256_array_to_int(list(response.current_units_inflow_x64))
+    )
+
+
+
+async def query_calc_local_swap_with_fee(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    swap_pool_asset_wallets: list[PublicKey],
+    from_asset: PublicKey,
+    to_asset: PublicKey,
+    amount: int,
+    vault_fee_bps: int,
+    approx: bool = False
+) -> int:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "calc_local_swap_with_fee",
+        from_asset,
+        to_asset,
+        amount,
+        vault_fee_bps,
+        approx,
+        ctx=Context(
+            accounts={"swap_pool_state_account": swap_pool_state},
+            remaining_accounts=[
+                AccountMeta(wallet, is_signer=False, is_writable=False) for wallet in swap_pool_asset_wallets
+            ]
+        )
+    )
+
+    return U64.parse(return_data)
+
+
+
+async def query_price_impact(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    swap_pool_asset_wallets: list[PublicKey],
+    from_asset: PublicKey,
+    to_asset: PublicKey,
+    amount: int
+) -> int:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "price_impact",
+        from_asset,
+        to_asset,
+        amount,
+        ctx=Context(
+            accounts={"swap_pool_state_account": swap_pool_state},
+            remaining_accounts=[
+                AccountMeta(wallet, is_signer=False, is_writable=False) for wallet in swap_pool_asset_wallets
+            ]
+        )
+    )
+
+    return U64.parse(return_data)
+
+
+
+async def query_depth_to_impact(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    swap_pool_asset_wallets: list[PublicKey],
+    from_asset: PublicKey,
+    to_asset: PublicKey,
+    max_impact_bps: int
+) -> int:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "depth_to_impact",
+        from_asset,
+        to_asset,
+        max_impact_bps,
+        ctx=Context(
+            accounts={"swap_pool_state_account": swap_pool_state},
+            remaining_accounts=[
+                AccountMeta(wallet, is_signer=False, is_writable=False) for wallet in swap_pool_asset_wallets
+            ]
+        )
+    )
+
+    return U64.parse(return_data)
+
+
+
+async def query_pool_token_supply_at(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    slot: int
+) -> int:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "pool_token_supply_at",
+        slot,
+        ctx=Context(accounts={"swap_pool_state_account": swap_pool_state})
+    )
+
+    return U64.parse(return_data)
+
+
+
+async def query_setup_deadline(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey
+) -> int:
+    """
+        Seconds left before the setup_master can no longer perform setup operations (0 once the setup is finished).
+    """
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "setup_deadline",
+        ctx=Context(accounts={"swap_pool_state_account": swap_pool_state})
+    )
+
+    return U64.parse(return_data)
+
+
+
+async def query_deposit_cap(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    asset: PublicKey
+) -> int:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "deposit_cap",
+        asset,
+        ctx=Context(accounts={"swap_pool_state_account": swap_pool_state})
+    )
+
+    return U64.parse(return_data)
+
+
+
+async def query_min_swap_amount(
+    swap_pool_program: Program,
+    swap_pool_state: PublicKey,
+    asset: PublicKey
+) -> int:
+
+    return_data = await simulate_view(
+        swap_pool_program,
+        "min_swap_amount",
+        asset,
+        ctx=Context(accounts={"swap_pool_state_account": swap_pool_state})
     )
 
+    return U64.parse(return_data)
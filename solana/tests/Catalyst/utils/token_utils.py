@@ -1,5 +1,6 @@
 """This module contains utilities for the SPL Token Program."""
 import asyncio
+from dataclasses import dataclass
 from solana.publickey import PublicKey
 from solana.keypair import Keypair
 from solana.transaction import Transaction
@@ -22,10 +23,21 @@ from spl.token.core import AccountInfo, MintInfo
 from solana.rpc.commitment import Commitment
 from spl.token._layouts import ACCOUNT_LAYOUT, MINT_LAYOUT
 import solana.system_program as sp
-from solana.rpc.types import TxOpts
+from solana.rpc.types import MemcmpOpts, TxOpts
 
 from utils.transaction_utils import DEFAULT_SKIP_PREFLIGHT, confirm_transaction, DEFAULT_TX_COMMITMENT
 
+DEFAULT_LIST_TOKEN_HOLDERS_LIMIT = 10
+MAX_LIST_TOKEN_HOLDERS_LIMIT     = 100
+
+
+@dataclass
+class TokenHolder:
+    wallet: PublicKey
+    owner: PublicKey
+    balance: int
+
+
 async def create_mint(
     provider: Provider,
     mint_authority: PublicKey,
@@ -309,6 +321,49 @@ async def get_mint_info(
 """
     Fund a single token account per provided mint. Optionally, set allowance for a third party.
 """
+async def list_token_holders(
+    provider: Provider,
+    mint: PublicKey,
+    start_after: PublicKey | None = None,
+    limit: int | None = None,
+    token_program_id: PublicKey = TOKEN_PROGRAM_ID,
+    commitment: Commitment | None = DEFAULT_TX_COMMITMENT
+) -> list[TokenHolder]:
+    """
+        List the token wallets of a mint with a nonzero balance, ordered by wallet address. Token wallets store their mint
+        as their first field, which allows to filter them directly from the rpc.
+    """
+
+    token_accounts = (await provider.connection.get_program_accounts(
+        token_program_id,
+        commitment,
+        encoding="base64",
+        filters=[ACCOUNT_LAYOUT.sizeof(), MemcmpOpts(offset=0, bytes=str(mint))]
+    )).value
+
+    holders = []
+    for token_account in token_accounts:
+        decoded_data = ACCOUNT_LAYOUT.parse(token_account.account.data)
+
+        if decoded_data.amount == 0:
+            continue
+
+        holders.append(TokenHolder(
+            wallet  = PublicKey(bytes(token_account.pubkey)),
+            owner   = PublicKey(decoded_data.owner),
+            balance = decoded_data.amount
+        ))
+
+    holders.sort(key=lambda holder: bytes(holder.wallet))
+
+    if start_after is not None:
+        holders = [holder for holder in holders if bytes(holder.wallet) > bytes(start_after)]
+
+    limit = min(limit or DEFAULT_LIST_TOKEN_HOLDERS_LIMIT, MAX_LIST_TOKEN_HOLDERS_LIMIT)
+
+    return holders[:limit]
+
+
 async def fund_accounts(
     provider: Provider,
     mints: list[PublicKey],
@@ -1,6 +1,7 @@
+import base64
 from types import TracebackType
 from typing import Any, Type, cast
-from anchorpy import EventParser, Program, Provider
+from anchorpy import Context, EventParser, Program, Provider
 from solders.signature import Signature
 from solders.rpc.responses import GetSignatureStatusesResp
 from solana.rpc.commitment import Commitment, Confirmed
@@ -32,6 +33,18 @@ async def confirm_transaction(provider: Provider, tx: Signature, commitment: Com
     return confirmation
 
 
+async def simulate_view(program: Program, instruction_name: str, *args: Any, ctx: Context) -> bytes:
+    """
+        Simulate a read-only ('query') instruction and return the raw data returned by it.
+    """
+    simulation = await program.simulate[instruction_name](*args, ctx=ctx)
+
+    return_log_prefix = f"Program return: {program.program_id} "
+    return_log = next(log for log in simulation.raw if log.startswith(return_log_prefix))
+
+    return base64.b64decode(return_log[len(return_log_prefix):])
+
+
 class TxEventListener():
 
     ws: SolanaWsClientProtocol
@@ -65,17 +78,22 @@ class TxEventListener():
         return self
     
 
-    async def get_events(self, program: Program):
+    async def get_events(self, program: Program, *other_programs: Program):
+        """
+            Get the events of the next transaction. The events emitted by other programs (e.g. via CPI) can be parsed
+            alongside the ones of 'program' by passing the other programs.
+        """
 
         events = []
 
         data = await self.ws.recv()
         logs: List[str] = data[0].result.value.logs   # type: ignore
 
-        EventParser(program.program_id, program.coder).parse_logs(
-            logs,
-            lambda event: events.append(event) if self.event_name is None or self.event_name == event.name else None
-        )
+        for parsed_program in [program, *other_programs]:
+            EventParser(parsed_program.program_id, parsed_program.coder).parse_logs(
+                logs,
+                lambda event: events.append(event) if self.event_name is None or self.event_name == event.name else None
+            )
 
         return events
 